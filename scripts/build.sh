@@ -4,3 +4,5 @@ set -e
 cargo --version
 echo build all
 cargo build --all-features
+echo build no_std library
+cargo build --lib --no-default-features
@@ -3,3 +3,4 @@ set -e
 
 cargo --version
 cargo clippy --all-features -- --deny warnings
+cargo clippy --lib --no-default-features -- --deny warnings
@@ -0,0 +1,20 @@
+//! Detection of common log format dates like `28/Feb/2024:20:43:09 +0000`, as written by
+//! Apache and nginx access logs.
+
+use std::{ops::Range, sync::LazyLock};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b\d\d/(?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/\d{4}:\d\d:\d\d:\d\d [+-]\d{4}\b")
+        .unwrap()
+});
+
+/// Returns an iterator over the common log format dates in `line`.
+pub fn dates(line: &str) -> impl Iterator<Item = (Range<usize>, DateTime<Utc>)> + '_ {
+    DATE.find_iter(line).filter_map(|m| {
+        let time = DateTime::parse_from_str(m.as_str(), "%d/%b/%Y:%H:%M:%S %z").ok()?;
+        Some((m.range(), time.to_utc()))
+    })
+}
@@ -0,0 +1,43 @@
+//! Timestamp arithmetic for `epoch add` and `epoch sub`.
+
+use std::io::{stdout, Write};
+
+use anyhow::Context;
+use epoch_to::{Precision, Unit};
+
+use crate::{duration, repl::parse_time, Reformatter};
+
+/// Prints `timestamp`, an epoch or RFC 3339 date-time, shifted by `duration_ns`: first
+/// as an epoch in the unit of the finer of their precisions, then formatted like any
+/// converted timestamp.
+pub fn shift(reformatter: &Reformatter, timestamp: &str, duration_ns: i64) -> anyhow::Result<()> {
+    let (time_ns, precision) = reformatter
+        .parse(timestamp)
+        .or_else(|| {
+            let (time, precision) = parse_time(timestamp)?;
+            Some((time.timestamp_nanos_opt()?, precision))
+        })
+        .with_context(|| {
+            format!(
+                "`{timestamp}` isn't an RFC 3339 date-time or a timestamp within the threshold \
+                 in any detected unit"
+            )
+        })?;
+    let time_ns = time_ns
+        .checked_add(duration_ns)
+        .context("the result is out of range")?;
+    let precision = precision.max(duration::precision(duration_ns));
+    let unit = match precision {
+        Precision::Days | Precision::Seconds => Unit::Seconds,
+        Precision::Millis => Unit::Millis,
+        Precision::Micros => Unit::Micros,
+        Precision::Nanos => Unit::Nanos,
+    };
+    let epoch = unit
+        .nanos_to_unit(time_ns, reformatter.epoch_base)
+        .context("the result is out of range")?;
+    let mut output = stdout().lock();
+    writeln!(output, "{epoch}")?;
+    writeln!(output, "{}", reformatter.format_time(time_ns, precision))?;
+    Ok(())
+}
@@ -0,0 +1,273 @@
+//! Conversion of MessagePack, CBOR, and DBN streams to JSON lines.
+
+use std::io::{BufRead, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{Map, Value};
+
+use crate::{glob_match, Precision, Reformatter};
+
+/// The CBOR tag for epoch-based date/times, in seconds.
+const CBOR_EPOCH_TAG: u64 = 1;
+/// The MessagePack extension type for timestamps.
+const MSGPACK_TIMESTAMP_EXT: i8 = -1;
+
+/// Converts values in binary formats to JSON, replacing timestamps with strings.
+pub struct Converter<'a> {
+    reformatter: &'a Reformatter,
+    /// Glob patterns of the keys whose integer values to convert. When empty, any integer
+    /// value detected as a timestamp is converted.
+    keys: &'a [String],
+}
+
+impl<'a> Converter<'a> {
+    pub fn new(reformatter: &'a Reformatter, keys: &'a [String]) -> Self {
+        Self { reformatter, keys }
+    }
+
+    /// Converts a stream of concatenated MessagePack values to JSON lines.
+    pub fn convert_msgpack<R: BufRead, W: Write>(
+        &self,
+        mut reader: R,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        while !reader.fill_buf()?.is_empty() {
+            let value = rmpv::decode::read_value(&mut reader)?;
+            serde_json::to_writer(&mut *writer, &self.msgpack_to_json(value, true))?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Converts a stream of concatenated CBOR values to JSON lines.
+    pub fn convert_cbor<R: BufRead, W: Write>(
+        &self,
+        mut reader: R,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        while !reader.fill_buf()?.is_empty() {
+            let value: ciborium::Value = ciborium::from_reader(&mut reader)?;
+            serde_json::to_writer(&mut *writer, &self.cbor_to_json(value, true))?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Converts a stream of Databento Binary Encoding (DBN) records to JSON lines. The
+    /// `ts_*` fields, or those matching the keys, are nanosecond timestamps, except for
+    /// `ts_in_delta` which is rendered as a duration.
+    #[cfg(feature = "dbn")]
+    pub fn convert_dbn<R: BufRead, W: Write>(
+        &self,
+        reader: R,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        use dbn::{
+            decode::{DecodeRecordRef, DynDecoder},
+            encode::{json, EncodeRecordRef},
+            VersionUpgradePolicy,
+        };
+
+        let mut decoder = DynDecoder::inferred_with_buffer(reader, VersionUpgradePolicy::AsIs)?;
+        let mut record_json = Vec::new();
+        while let Some(record) = decoder.decode_record_ref()? {
+            record_json.clear();
+            json::Encoder::new(&mut record_json, false, false, false).encode_record_ref(record)?;
+            let value: Value = serde_json::from_slice(&record_json)?;
+            serde_json::to_writer(&mut *writer, &self.dbn_to_json(value))?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "dbn")]
+    fn dbn_to_json(&self, value: Value) -> Value {
+        let Value::Object(fields) = value else {
+            return value;
+        };
+        Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let is_timestamp_key = if self.keys.is_empty() {
+                        key.starts_with("ts_")
+                    } else {
+                        self.keys.iter().any(|pattern| glob_match(pattern, &key))
+                    };
+                    // Integers wider than 32 bits are encoded as strings
+                    let n = match &value {
+                        Value::String(s) => s.parse::<i64>().ok(),
+                        value => value.as_i64(),
+                    };
+                    let value = match n {
+                        Some(n) if is_timestamp_key && key == "ts_in_delta" => {
+                            Value::String(crate::duration::format(n))
+                        }
+                        Some(n) if is_timestamp_key => {
+                            Value::String(self.reformatter.format_time(n, Precision::Nanos))
+                        }
+                        // The maximum value is the sentinel for a missing timestamp
+                        None if is_timestamp_key
+                            && value.as_str() == Some(&u64::MAX.to_string()) =>
+                        {
+                            Value::Null
+                        }
+                        _ => self.dbn_to_json(value),
+                    };
+                    (key, value)
+                })
+                .collect(),
+        )
+    }
+
+    fn msgpack_to_json(&self, value: rmpv::Value, is_timestamp_key: bool) -> Value {
+        use rmpv::Value as Mp;
+
+        match value {
+            Mp::Nil => Value::Null,
+            Mp::Boolean(b) => Value::Bool(b),
+            Mp::Integer(i) => match i.as_i64() {
+                Some(n) => self.integer_to_json(n, is_timestamp_key),
+                None => i.as_u64().map_or(Value::Null, Value::from),
+            },
+            Mp::F32(f) => Value::from(f),
+            Mp::F64(f) => Value::from(f),
+            Mp::String(s) => Value::String(s.into_str().unwrap_or_default()),
+            Mp::Binary(bytes) => Value::String(STANDARD.encode(bytes)),
+            Mp::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|v| self.msgpack_to_json(v, is_timestamp_key))
+                    .collect(),
+            ),
+            Mp::Map(entries) => self.map_to_json(
+                entries.into_iter().map(|(k, v)| {
+                    let key = match k {
+                        Mp::String(s) => s.into_str().unwrap_or_default(),
+                        k => k.to_string(),
+                    };
+                    (key, v)
+                }),
+                Self::msgpack_to_json,
+            ),
+            Mp::Ext(MSGPACK_TIMESTAMP_EXT, data) => {
+                msgpack_timestamp(&data).map_or(Value::Null, |(time_ns, precision)| {
+                    Value::String(self.reformatter.format_time(time_ns, precision))
+                })
+            }
+            Mp::Ext(ty, data) => {
+                let mut ext = Map::new();
+                ext.insert("type".to_owned(), Value::from(ty));
+                ext.insert("data".to_owned(), Value::String(STANDARD.encode(data)));
+                Value::Object(ext)
+            }
+        }
+    }
+
+    fn cbor_to_json(&self, value: ciborium::Value, is_timestamp_key: bool) -> Value {
+        use ciborium::Value as Cbor;
+
+        match value {
+            Cbor::Null => Value::Null,
+            Cbor::Bool(b) => Value::Bool(b),
+            Cbor::Integer(i) => match i64::try_from(i) {
+                Ok(n) => self.integer_to_json(n, is_timestamp_key),
+                Err(_) => Value::from(i128::from(i) as f64),
+            },
+            Cbor::Float(f) => Value::from(f),
+            Cbor::Text(s) => Value::String(s),
+            Cbor::Bytes(bytes) => Value::String(STANDARD.encode(bytes)),
+            Cbor::Tag(CBOR_EPOCH_TAG, value) => {
+                let (time_ns, precision) = match *value {
+                    Cbor::Integer(i) => (
+                        i64::try_from(i)
+                            .ok()
+                            .and_then(|s| s.checked_mul(1_000_000_000)),
+                        Precision::Seconds,
+                    ),
+                    Cbor::Float(s) => (
+                        Some((s * 1e9).round())
+                            .filter(|ns| ns.abs() < i64::MAX as f64)
+                            .map(|ns| ns as i64),
+                        Precision::Nanos,
+                    ),
+                    _ => (None, Precision::Seconds),
+                };
+                time_ns.map_or(Value::Null, |time_ns| {
+                    Value::String(self.reformatter.format_time(time_ns, precision))
+                })
+            }
+            Cbor::Tag(_, value) => self.cbor_to_json(*value, is_timestamp_key),
+            Cbor::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(|v| self.cbor_to_json(v, is_timestamp_key))
+                    .collect(),
+            ),
+            Cbor::Map(entries) => self.map_to_json(
+                entries.into_iter().map(|(k, v)| {
+                    let key = match k {
+                        Cbor::Text(s) => s,
+                        k => {
+                            serde_json::to_string(&self.cbor_to_json(k, false)).unwrap_or_default()
+                        }
+                    };
+                    (key, v)
+                }),
+                Self::cbor_to_json,
+            ),
+            _ => Value::Null,
+        }
+    }
+
+    fn map_to_json<V>(
+        &self,
+        entries: impl Iterator<Item = (String, V)>,
+        to_json: fn(&Self, V, bool) -> Value,
+    ) -> Value {
+        Value::Object(
+            entries
+                .map(|(key, value)| {
+                    let is_timestamp_key = self.keys.is_empty()
+                        || self.keys.iter().any(|pattern| glob_match(pattern, &key));
+                    let value = to_json(self, value, is_timestamp_key);
+                    (key, value)
+                })
+                .collect(),
+        )
+    }
+
+    fn integer_to_json(&self, n: i64, is_timestamp_key: bool) -> Value {
+        if is_timestamp_key {
+            if let Some((time_ns, precision)) = self.reformatter.parse(&n.to_string()) {
+                return Value::String(self.reformatter.format_time(time_ns, precision));
+            }
+        }
+        Value::from(n)
+    }
+}
+
+/// Decodes the MessagePack timestamp extension to nanoseconds since the UNIX epoch.
+fn msgpack_timestamp(data: &[u8]) -> Option<(i64, Precision)> {
+    let (s, ns) = match data.len() {
+        // Seconds only
+        4 => {
+            let s = i64::from(u32::from_be_bytes(data.try_into().ok()?));
+            return Some((s.checked_mul(1_000_000_000)?, Precision::Seconds));
+        }
+        8 => {
+            let n = u64::from_be_bytes(data.try_into().ok()?);
+            // 30-bit nanoseconds followed by 34-bit seconds
+            ((n & 0x3_ffff_ffff) as i64, (n >> 34) as i64)
+        }
+        12 => (
+            i64::from_be_bytes(data[4..].try_into().ok()?),
+            i64::from(u32::from_be_bytes(data[..4].try_into().ok()?)),
+        ),
+        _ => return None,
+    };
+    Some((
+        s.checked_mul(1_000_000_000)?.checked_add(ns)?,
+        Precision::Nanos,
+    ))
+}
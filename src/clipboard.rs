@@ -0,0 +1,18 @@
+//! Access to the system clipboard.
+
+use anyhow::Context;
+use arboard::Clipboard;
+
+/// Returns the text on the clipboard.
+pub fn paste() -> anyhow::Result<String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .context("failed to read the clipboard")
+}
+
+/// Replaces the contents of the clipboard with `text`.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .context("failed to write to the clipboard")
+}
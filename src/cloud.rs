@@ -0,0 +1,210 @@
+//! Resolution of `s3://` and `gs://` object URLs to HTTPS requests.
+//!
+//! Credentials come from the same environment variables as the official tools:
+//! `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and optionally `AWS_SESSION_TOKEN`,
+//! `AWS_REGION`, and `AWS_ENDPOINT_URL` for S3, and `GOOGLE_OAUTH_ACCESS_TOKEN` for GCS.
+//! Without them, objects are requested anonymously, which only works for public ones.
+//! Gzipped objects, like most archived logs, are decompressed.
+
+use std::{
+    env,
+    fmt::Write,
+    io::{BufRead, BufReader},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use flate2::bufread::MultiGzDecoder;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+
+/// Returns `true` if `input` is an object URL.
+pub fn is_object_url(input: &str) -> bool {
+    input.starts_with("s3://") || input.starts_with("gs://")
+}
+
+/// Returns the HTTPS URL and headers to request the object at `url`.
+pub fn request(url: &str) -> anyhow::Result<(String, Vec<(String, String)>)> {
+    let (scheme, path) = url.split_once("://").context("expected an object URL")?;
+    let (bucket, key) = path
+        .split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .with_context(|| format!("expected `{scheme}://BUCKET/KEY`, found `{url}`"))?;
+    let key = encode_path(key);
+    if scheme == "gs" {
+        let url = format!("https://storage.googleapis.com/{bucket}/{key}");
+        let headers = env::var("GOOGLE_OAUTH_ACCESS_TOKEN")
+            .map(|token| ("Authorization".to_owned(), format!("Bearer {token}")))
+            .into_iter()
+            .collect();
+        return Ok((url, headers));
+    }
+    let region = env::var("AWS_REGION")
+        .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_owned());
+    // Custom endpoints like MinIO use path-style URLs
+    let (url, host, path) =
+        match env::var("AWS_ENDPOINT_URL_S3").or_else(|_| env::var("AWS_ENDPOINT_URL")) {
+            Ok(endpoint) => {
+                let endpoint = endpoint.trim_end_matches('/');
+                let host = endpoint
+                    .split_once("://")
+                    .map_or(endpoint, |(_, host)| host)
+                    .to_owned();
+                let path = format!("/{bucket}/{key}");
+                (format!("{endpoint}{path}"), host, path)
+            }
+            Err(_) => {
+                let host = format!("{bucket}.s3.{region}.amazonaws.com");
+                (format!("https://{host}/{key}"), host, format!("/{key}"))
+            }
+        };
+    let (Ok(access_key), Ok(secret_key)) = (
+        env::var("AWS_ACCESS_KEY_ID"),
+        env::var("AWS_SECRET_ACCESS_KEY"),
+    ) else {
+        return Ok((url, Vec::new()));
+    };
+    let credentials = Credentials {
+        access_key,
+        secret_key,
+        session_token: env::var("AWS_SESSION_TOKEN").ok(),
+    };
+    let headers = credentials.sign(&host, &path, &region, Utc::now());
+    Ok((url, headers))
+}
+
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns a reader over the decompressed contents of `object` if it's gzipped, or else
+/// `object` itself. This goes by the contents rather than a `.gz` extension, since
+/// objects stored with `Content-Encoding: gzip` arrive already decompressed.
+pub fn decompress(mut object: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    let start = object.fill_buf().context("failed to read the object")?;
+    if start.starts_with(&GZIP_MAGIC) {
+        return Ok(Box::new(BufReader::new(MultiGzDecoder::new(object))));
+    }
+    Ok(object)
+}
+
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Returns the headers of an AWS Signature Version 4 signed `GET` request for the
+    /// object at `path` on `host`. The payload is left unsigned, which S3 allows.
+    fn sign(
+        &self,
+        host: &str,
+        path: &str,
+        region: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<(String, String)> {
+        let date_time = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        // Headers to sign, sorted by name
+        let mut headers = vec![
+            ("host".to_owned(), host.to_owned()),
+            (
+                "x-amz-content-sha256".to_owned(),
+                "UNSIGNED-PAYLOAD".to_owned(),
+            ),
+            ("x-amz-date".to_owned(), date_time.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token".to_owned(), token.clone()));
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request =
+            format!("GET\n{path}\n\n{canonical_headers}\n{signed_headers}\nUNSIGNED-PAYLOAD");
+        let scope = format!("{date}/{region}/s3/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{date_time}\n{scope}\n{}",
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = [region, "s3", "aws4_request"].iter().fold(
+            hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date),
+            |key, part| hmac(&key, part),
+        );
+        let signature = hex(&hmac(&key, &string_to_sign));
+        // The host header is set by the HTTP client
+        headers.remove(0);
+        headers.push((
+            "Authorization".to_owned(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+                 Signature={signature}",
+                self.access_key
+            ),
+        ));
+        headers
+    }
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+/// Percent-encodes `path` except for unreserved characters and `/`.
+fn encode_path(path: &str) -> String {
+    path.bytes().fold(String::new(), |mut encoded, b| {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(b as char);
+        } else {
+            let _ = write!(encoded, "%{b:02X}");
+        }
+        encoded
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn read(object: Vec<u8>) -> String {
+        let mut contents = String::new();
+        decompress(Box::new(std::io::Cursor::new(object)))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_decompress() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"ts=1709152989\n").unwrap();
+        assert_eq!(read(encoder.finish().unwrap()), "ts=1709152989\n");
+    }
+
+    #[test]
+    fn test_decompress_plain() {
+        assert_eq!(read(b"ts=1709152989\n".to_vec()), "ts=1709152989\n");
+        assert_eq!(read(Vec::new()), "");
+    }
+}
@@ -0,0 +1,147 @@
+//! Printing the rows of Parquet files for `epoch parquet`.
+
+use std::{
+    fs::File,
+    io::{stdout, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        Date32Type, Date64Type, TimestampMicrosecondType, TimestampMillisecondType,
+        TimestampNanosecondType, TimestampSecondType,
+    },
+    Array, RecordBatch, RecordBatchReader,
+};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, TimeUnit};
+use chrono::{DateTime, NaiveDate};
+use parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ProjectionMask};
+
+use crate::{Precision, Reformatter};
+
+/// Prints the rows of the Parquet file at `path` as tab-separated values with a header,
+/// formatting timestamp and date columns. Only `columns` are printed if it's not empty.
+pub fn print_parquet(
+    reformatter: &Reformatter,
+    path: &Path,
+    columns: &[String],
+) -> anyhow::Result<()> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    if !columns.is_empty() {
+        let fields = builder.schema().fields();
+        let mut roots = Vec::with_capacity(columns.len());
+        for column in columns {
+            let Some(i) = fields.iter().position(|field| field.name() == column) else {
+                bail!(
+                    "no column `{column}` in {}, expected one of {}",
+                    path.display(),
+                    fields
+                        .iter()
+                        .map(|field| field.name().as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+            roots.push(i);
+        }
+        let mask = ProjectionMask::roots(builder.parquet_schema(), roots);
+        builder = builder.with_projection(mask);
+    }
+    let reader = builder.build()?;
+
+    let mut writer = BufWriter::new(stdout().lock());
+    let header = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect::<Vec<_>>()
+        .join("\t");
+    writeln!(writer, "{header}")?;
+    for batch in reader {
+        let batch = batch.with_context(|| format!("failed to read {}", path.display()))?;
+        write_batch(reformatter, &mut writer, &batch)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_batch<W: Write>(
+    reformatter: &Reformatter,
+    writer: &mut W,
+    batch: &RecordBatch,
+) -> anyhow::Result<()> {
+    let options = FormatOptions::default();
+    let formatters = batch
+        .columns()
+        .iter()
+        .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+        .collect::<Result<Vec<_>, _>>()?;
+    for row in 0..batch.num_rows() {
+        for (i, column) in batch.columns().iter().enumerate() {
+            if i > 0 {
+                write!(writer, "\t")?;
+            }
+            if column.is_null(row) {
+                continue;
+            }
+            match format_value(reformatter, column.as_ref(), row) {
+                Some(formatted) => write!(writer, "{formatted}")?,
+                None => write!(writer, "{}", formatters[i].value(row))?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Formats the value at `row` of `column` if it's a timestamp or date, or returns `None`
+/// for other types.
+fn format_value(reformatter: &Reformatter, column: &dyn Array, row: usize) -> Option<String> {
+    let (time_ns, precision) = match column.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => (
+            column
+                .as_primitive::<TimestampSecondType>()
+                .value(row)
+                .checked_mul(1_000_000_000)?,
+            Precision::Seconds,
+        ),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => (
+            column
+                .as_primitive::<TimestampMillisecondType>()
+                .value(row)
+                .checked_mul(1_000_000)?,
+            Precision::Millis,
+        ),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => (
+            column
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(row)
+                .checked_mul(1_000)?,
+            Precision::Micros,
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => (
+            column.as_primitive::<TimestampNanosecondType>().value(row),
+            Precision::Nanos,
+        ),
+        // Dates are calendar days without a zone, so they aren't converted
+        DataType::Date32 => {
+            let days = column.as_primitive::<Date32Type>().value(row);
+            let date =
+                NaiveDate::default().checked_add_signed(chrono::Duration::days(days.into()))?;
+            return Some(date.format("%Y-%m-%d").to_string());
+        }
+        DataType::Date64 => {
+            let millis = column.as_primitive::<Date64Type>().value(row);
+            let date = DateTime::from_timestamp_millis(millis)?.date_naive();
+            return Some(date.format("%Y-%m-%d").to_string());
+        }
+        _ => return None,
+    };
+    Some(reformatter.format_time(time_ns, precision))
+}
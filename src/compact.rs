@@ -0,0 +1,38 @@
+//! Detection of compact dates like `20240228` and date-times like `20240228204309`, as
+//! used in file names and batch job logs.
+
+use std::{ops::Range, sync::LazyLock};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use epoch_to::Precision;
+use regex::Regex;
+
+static DIGITS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[0-9]+").unwrap());
+
+/// Returns an iterator over the compact dates in `line` that are real calendar dates
+/// within `window`, in seconds since the UNIX epoch, where `to_utc` converts a
+/// wall-clock time to UTC along with a flag that's passed through.
+pub fn dates<'a, T: 'a>(
+    line: &'a str,
+    window: Range<i64>,
+    to_utc: impl Fn(NaiveDateTime) -> Option<(DateTime<Utc>, T)> + 'a,
+) -> impl Iterator<Item = (Range<usize>, (DateTime<Utc>, T), Precision)> + 'a {
+    DIGITS.find_iter(line).filter_map(move |m| {
+        let digits = m.as_str();
+        let (wall_time, precision) = match digits.len() {
+            8 => (
+                NaiveDate::parse_from_str(digits, "%Y%m%d")
+                    .ok()?
+                    .and_time(NaiveTime::MIN),
+                Precision::Days,
+            ),
+            14 => (
+                NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M%S").ok()?,
+                Precision::Seconds,
+            ),
+            _ => return None,
+        };
+        let time = to_utc(wall_time).filter(|(time, _)| window.contains(&time.timestamp()))?;
+        Some((m.range(), time, precision))
+    })
+}
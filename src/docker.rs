@@ -0,0 +1,178 @@
+//! Reading container logs from the Docker Engine API for `epoch docker`.
+
+use std::{
+    env,
+    io::{stderr, BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context};
+
+use crate::{output, Args, Reformatter};
+
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+/// Writes the logs of `container` with their timestamps converted, its standard output
+/// to the output and its standard error to STDERR. Keeps writing new lines with `follow`.
+pub fn docker(args: &Args, container: &str, follow: bool) -> anyhow::Result<()> {
+    let reformatter = Reformatter::new(args)?;
+    let socket = socket_path()?;
+    let mut stream = UnixStream::connect(&socket)
+        .with_context(|| format!("failed to connect to {}", socket.display()))?;
+    // HTTP/1.0 responses aren't chunked, so the body is the rest of the stream
+    write!(
+        stream,
+        "GET /containers/{}/logs?stdout=1&stderr=1&follow={} HTTP/1.0\r\nHost: docker\r\n\r\n",
+        percent_encode(container),
+        u8::from(follow),
+    )?;
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    let mut is_multiplexed = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-type") {
+                is_multiplexed = Some(value.trim() == "application/vnd.docker.multiplexed-stream");
+            }
+        }
+    }
+    if status.split_whitespace().nth(1) != Some("200") {
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|body| body["message"].as_str().map(str::to_owned))
+            .unwrap_or(body);
+        bail!("failed to read the logs of {container}: {}", message.trim());
+    }
+
+    // Followed logs never finish, so they're written in place
+    let mut output = output::open(&Args {
+        no_atomic: args.no_atomic || follow,
+        ..args.clone()
+    })?;
+    let mut lines = [Lines::default(), Lines::default()];
+    // Containers without a TTY multiplex their standard output and error in frames with
+    // an 8-byte header of the stream and the frame's length. Older engines don't say
+    // which, but the header's first 4 bytes are distinctive
+    let is_multiplexed = match is_multiplexed {
+        Some(is_multiplexed) => is_multiplexed,
+        None => {
+            let buf = reader.fill_buf()?;
+            buf.len() >= 8 && buf[0] <= 2 && buf[1..4] == [0, 0, 0]
+        }
+    };
+    if is_multiplexed {
+        let mut header = [0; 8];
+        let mut frame = Vec::new();
+        loop {
+            match reader.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+            let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+            frame.resize(len as usize, 0);
+            reader.read_exact(&mut frame)?;
+            // Standard input is never logged, so the stream is either 1 or 2
+            if header[0] == 2 {
+                lines[1].write(&reformatter, &frame, &mut stderr().lock(), follow)?;
+            } else {
+                lines[0].write(&reformatter, &frame, &mut output, follow)?;
+            }
+        }
+    } else {
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let len = buf.len();
+            lines[0].write(&reformatter, buf, &mut output, follow)?;
+            reader.consume(len);
+        }
+    }
+    let [out, err] = lines;
+    out.finish(&reformatter, &mut output)?;
+    err.finish(&reformatter, &mut stderr().lock())?;
+    output.finish()
+}
+
+/// The path of the Docker Engine socket, from `DOCKER_HOST` if it's a `unix://` URL,
+/// like Podman's.
+fn socket_path() -> anyhow::Result<PathBuf> {
+    let Some(host) = env::var_os("DOCKER_HOST") else {
+        return Ok(PathBuf::from(DEFAULT_SOCKET));
+    };
+    let host = host.to_string_lossy();
+    match host.strip_prefix("unix://") {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => bail!("expected `DOCKER_HOST` to be a `unix://` socket, found `{host}`"),
+    }
+}
+
+/// Percent-encodes the characters of a container name or ID that aren't allowed in a
+/// URL path segment.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.') {
+                char::from(b).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+/// A stream's lines, which can be split between frames.
+#[derive(Default)]
+struct Lines {
+    /// The start of a line that hasn't ended yet.
+    partial: Vec<u8>,
+    line_no: usize,
+}
+
+impl Lines {
+    /// Writes the complete lines of `data` with their timestamps converted, keeping the
+    /// rest for the next frame.
+    fn write(
+        &mut self,
+        reformatter: &Reformatter,
+        data: &[u8],
+        writer: &mut dyn Write,
+        flush: bool,
+    ) -> anyhow::Result<()> {
+        self.partial.extend_from_slice(data);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(());
+        };
+        for line in self.partial[..end].split(|&b| b == b'\n') {
+            self.line_no += 1;
+            reformatter.start_line(self.line_no);
+            let line = String::from_utf8_lossy(line);
+            writeln!(writer, "{}", reformatter.convert(&line)?)?;
+        }
+        self.partial.drain(..=end);
+        if flush {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the last line if it didn't end with a newline.
+    fn finish(self, reformatter: &Reformatter, writer: &mut dyn Write) -> anyhow::Result<()> {
+        if !self.partial.is_empty() {
+            reformatter.start_line(self.line_no + 1);
+            let line = String::from_utf8_lossy(&self.partial);
+            write!(writer, "{}", reformatter.convert(&line)?)?;
+        }
+        Ok(())
+    }
+}
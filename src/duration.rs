@@ -0,0 +1,114 @@
+//! Detection and reformatting of durations like `547261us`.
+
+use std::{ops::Range, sync::LazyLock};
+
+use epoch_to::Precision;
+use regex::Regex;
+
+static DURATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d+)(?:\.(\d+))?(ns|us|µs|ms|s)\b").unwrap());
+
+/// A duration argument made of one or more components like `1h30m`.
+static DURATION_ARG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:\d+(?:d|h|ms|us|µs|ns|m|s))+$").unwrap());
+static COMPONENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+)(d|h|ms|us|µs|ns|m|s)").unwrap());
+
+/// The units durations are rendered in, from largest to smallest, with their length in
+/// nanoseconds.
+const UNITS: [(&str, u64); 4] = [
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+/// Returns an iterator over the durations in `line` along with their reformatted text in
+/// the largest unit that keeps the value at least 1.
+pub fn durations(line: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    DURATION.captures_iter(line).filter_map(|caps| {
+        let unit_ns = match &caps[3] {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            _ => 1_000_000_000,
+        };
+        let nanos = to_nanos(&caps[1], caps.get(2).map_or("", |m| m.as_str()), unit_ns)?;
+        Some((
+            caps.get(0).unwrap().range(),
+            format(i64::try_from(nanos).ok()?),
+        ))
+    })
+}
+
+/// Parses a duration argument like `1h30m`, `250ms`, or `3d` to nanoseconds.
+pub fn parse(s: &str) -> Result<i64, String> {
+    let error = || format!("expected a duration like `1h30m`, `250ms`, or `3d`, found `{s}`");
+    if !DURATION_ARG.is_match(s) {
+        return Err(error());
+    }
+    COMPONENT.captures_iter(s).try_fold(0i64, |nanos, caps| {
+        let unit_ns = match &caps[2] {
+            "d" => 86_400_000_000_000,
+            "h" => 3_600_000_000_000,
+            "m" => 60_000_000_000,
+            "s" => 1_000_000_000,
+            "ms" => 1_000_000,
+            "us" | "µs" => 1_000,
+            _ => 1,
+        };
+        caps[1]
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit_ns))
+            .and_then(|n| nanos.checked_add(n))
+            .ok_or_else(|| format!("duration `{s}` is too long"))
+    })
+}
+
+/// Returns the coarsest precision, at least seconds, that represents a duration of
+/// `nanos` exactly.
+pub fn precision(nanos: i64) -> Precision {
+    match nanos {
+        _ if nanos % 1_000_000_000 == 0 => Precision::Seconds,
+        _ if nanos % 1_000_000 == 0 => Precision::Millis,
+        _ if nanos % 1_000 == 0 => Precision::Micros,
+        _ => Precision::Nanos,
+    }
+}
+
+/// Converts the decimal `integer.fraction` in a unit of `unit_ns` nanoseconds to
+/// nanoseconds, truncating any sub-nanosecond part.
+fn to_nanos(integer: &str, fraction: &str, unit_ns: u64) -> Option<u64> {
+    let mut nanos = integer.parse::<u64>().ok()?.checked_mul(unit_ns)?;
+    let mut scale = unit_ns;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        if scale == 0 {
+            break;
+        }
+        nanos = nanos.checked_add(u64::from(digit - b'0') * scale)?;
+    }
+    Some(nanos)
+}
+
+/// Formats a signed duration in nanoseconds like `-547.261µs`.
+pub fn format(nanos: i64) -> String {
+    let sign = if nanos < 0 { "-" } else { "" };
+    format!("{sign}{}", format_nanos(nanos.unsigned_abs()))
+}
+
+fn format_nanos(nanos: u64) -> String {
+    let (name, unit_ns) = UNITS
+        .into_iter()
+        .find(|(_, unit_ns)| nanos >= *unit_ns)
+        .unwrap_or(("ns", 1));
+    let integer = nanos / unit_ns;
+    let fraction = nanos % unit_ns;
+    if fraction == 0 {
+        return format!("{integer}{name}");
+    }
+    let width = unit_ns.ilog10() as usize;
+    let fraction = format!("{fraction:0width$}");
+    format!("{integer}.{}{name}", fraction.trim_end_matches('0'))
+}
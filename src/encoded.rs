@@ -0,0 +1,121 @@
+//! Detection of base64-encoded payloads.
+
+use std::{ops::Range, sync::LazyLock};
+
+use base64::{
+    engine::general_purpose::{
+        STANDARD, STANDARD_NO_PAD, STANDARD_PAD_INDIFFERENT, URL_SAFE, URL_SAFE_NO_PAD,
+        URL_SAFE_PAD_INDIFFERENT,
+    },
+    Engine,
+};
+use clap::ValueEnum;
+use regex::Regex;
+
+/// Shorter runs are too likely to be ordinary words or identifiers.
+const MIN_LEN: usize = 16;
+
+/// JSON Web Tokens, whose header and payload are base64url-encoded JSON objects and so
+/// start with `eyJ`.
+static JWT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\beyJ[A-Za-z0-9_-]+\.(eyJ[A-Za-z0-9_-]+)\.[A-Za-z0-9_-]*").unwrap()
+});
+
+/// The registered JWT claims that are times.
+const JWT_TIME_CLAIMS: [&str; 3] = ["iat", "nbf", "exp"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Base64Mode {
+    /// Replace the payload with the re-encoded converted payload
+    Reencode,
+    /// Keep the payload and append the decoded, converted payload
+    Annotate,
+}
+
+/// A base64-encoded payload.
+#[derive(Debug)]
+pub struct Payload {
+    pub range: Range<usize>,
+    pub bytes: Vec<u8>,
+    url_safe: bool,
+    padded: bool,
+}
+
+impl Payload {
+    /// Returns the payload as text if it's printable UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.bytes)
+            .ok()
+            .filter(|text| text.chars().all(|c| !c.is_control() || c.is_whitespace()))
+    }
+
+    /// Encodes `text` with the same alphabet and padding as this payload.
+    pub fn encode(&self, text: &str) -> String {
+        match (self.url_safe, self.padded) {
+            (false, true) => STANDARD.encode(text),
+            (false, false) => STANDARD_NO_PAD.encode(text),
+            (true, true) => URL_SAFE.encode(text),
+            (true, false) => URL_SAFE_NO_PAD.encode(text),
+        }
+    }
+}
+
+/// Returns an iterator over the runs of base64 characters in `line` that decode
+/// successfully.
+pub fn payloads(line: &str) -> impl Iterator<Item = Payload> + '_ {
+    let bytes = line.as_bytes();
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while pos < bytes.len() {
+            let start = pos + bytes[pos..].iter().position(|&b| is_base64(b))?;
+            let mut end = start + bytes[start..].iter().take_while(|&&b| is_base64(b)).count();
+            let unpadded_end = end;
+            end += bytes[end..]
+                .iter()
+                .take(2)
+                .take_while(|&&b| b == b'=')
+                .count();
+            pos = end;
+            if end - start < MIN_LEN {
+                continue;
+            }
+            let encoded = &line[start..end];
+            let url_safe = encoded.contains(['-', '_']);
+            let engine = if url_safe {
+                URL_SAFE_PAD_INDIFFERENT
+            } else {
+                STANDARD_PAD_INDIFFERENT
+            };
+            let Ok(bytes) = engine.decode(encoded) else {
+                continue;
+            };
+            return Some(Payload {
+                range: start..end,
+                bytes,
+                url_safe,
+                padded: end > unpadded_end,
+            });
+        }
+        None
+    })
+}
+
+fn is_base64(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'-' | b'_')
+}
+
+/// Returns an iterator over the JWTs in `line` along with their time claims in seconds
+/// since the UNIX epoch. Signatures aren't verified.
+pub fn jwt_claims(
+    line: &str,
+) -> impl Iterator<Item = (Range<usize>, Vec<(&'static str, f64)>)> + '_ {
+    JWT.captures_iter(line).filter_map(|caps| {
+        let payload = URL_SAFE_PAD_INDIFFERENT.decode(&caps[1]).ok()?;
+        let payload: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+        let claims: Vec<_> = JWT_TIME_CLAIMS
+            .into_iter()
+            .filter_map(|claim| Some((claim, payload.get(claim)?.as_f64()?)))
+            .collect();
+        (!claims.is_empty()).then(|| (caps.get(0).unwrap().range(), claims))
+    })
+}
@@ -0,0 +1,55 @@
+//! Transcoding of input in other character encodings to UTF-8 for `--encoding`.
+
+use std::io::{BufRead, BufReader};
+
+use clap::ValueEnum;
+use encoding_rs::{UTF_16BE, UTF_16LE, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// UTF-8, which is passed through as is apart from a byte order mark
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    /// UTF-16 little-endian, like most Windows text files
+    #[value(name = "utf-16le")]
+    Utf16le,
+    /// UTF-16 big-endian
+    #[value(name = "utf-16be")]
+    Utf16be,
+    /// ISO 8859-1, decoded as its superset Windows-1252
+    #[value(name = "latin1")]
+    Latin1,
+    /// UTF-16 or UTF-8 by the byte order mark at the start of the input, or UTF-8
+    /// without one
+    Auto,
+}
+
+/// Returns `input` transcoded from `encoding` to UTF-8. A byte order mark at the start
+/// of the input takes precedence over `encoding` and is removed.
+pub fn decode(input: Box<dyn BufRead>, encoding: Encoding) -> Box<dyn BufRead> {
+    let encoding = match encoding {
+        Encoding::Utf8 => return skip_utf8_bom(input),
+        Encoding::Utf16le => Some(UTF_16LE),
+        Encoding::Utf16be => Some(UTF_16BE),
+        Encoding::Latin1 => Some(WINDOWS_1252),
+        Encoding::Auto => None,
+    };
+    Box::new(BufReader::new(
+        DecodeReaderBytesBuilder::new()
+            .encoding(encoding)
+            .build(input),
+    ))
+}
+
+/// Returns `input` without a UTF-8 byte order mark at its start. The rest is passed
+/// through as is, unlike when decoding, which would replace invalid UTF-8.
+fn skip_utf8_bom(mut input: Box<dyn BufRead>) -> Box<dyn BufRead> {
+    if input
+        .fill_buf()
+        .is_ok_and(|start| start.starts_with(b"\xef\xbb\xbf"))
+    {
+        input.consume(3);
+    }
+    input
+}
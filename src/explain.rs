@@ -0,0 +1,76 @@
+//! A breakdown of a single timestamp for `epoch explain`.
+
+use std::io::{stdout, Write};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    repl::{renderings, unit_name, UNITS},
+    Reformatter,
+};
+
+/// Prints how `number` is detected and what time it represents in different zones,
+/// calendars, and units.
+pub fn explain(reformatter: &Reformatter, number: &str) -> anyhow::Result<()> {
+    let (time_ns, precision) = reformatter.parse(number).with_context(|| {
+        format!("`{number}` isn't a timestamp within the threshold in any detected unit")
+    })?;
+    let time = DateTime::from_timestamp_nanos(time_ns);
+    let others = reformatter.other_unit_names(number);
+    let mut rows = vec![("unit".to_owned(), {
+        let unit = reformatter.unit_name(number, precision);
+        if others.is_empty() {
+            unit
+        } else {
+            format!("{unit} (also in range as {})", others.join(", "))
+        }
+    })];
+    rows.extend(renderings(reformatter, time_ns, precision));
+    let date = reformatter.zone.wall_time(time).date();
+    rows.push(("weekday".to_owned(), date.format("%A").to_string()));
+    rows.push(("ISO week".to_owned(), date.format("%G-W%V").to_string()));
+    rows.push(("relative".to_owned(), relative(time, Utc::now())));
+    rows.extend(UNITS.iter().filter_map(|&unit| {
+        let n = unit.nanos_to_unit(time_ns, reformatter.epoch_base)?;
+        Some((unit_name(unit), n.to_string()))
+    }));
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut output = stdout().lock();
+    for (label, value) in rows {
+        writeln!(output, "{label:<width$}  {value}")?;
+    }
+    Ok(())
+}
+
+/// Describes how long ago or from now `time` is in its two largest units.
+pub fn relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    const UNITS: [(&str, i64); 5] = [
+        ("year", 365 * 86_400),
+        ("day", 86_400),
+        ("hour", 3_600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let delta = (time - now).num_seconds();
+    let mut rest = delta.unsigned_abs() as i64;
+    let parts: Vec<_> = UNITS
+        .iter()
+        .filter_map(|&(name, seconds)| {
+            let n = rest / seconds;
+            rest %= seconds;
+            match n {
+                0 => None,
+                1 => Some(format!("1 {name}")),
+                _ => Some(format!("{n} {name}s")),
+            }
+        })
+        .take(2)
+        .collect();
+    match (parts.is_empty(), delta < 0) {
+        (true, _) => "now".to_owned(),
+        (false, true) => format!("{} ago", parts.join(" ")),
+        (false, false) => format!("in {}", parts.join(" ")),
+    }
+}
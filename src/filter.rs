@@ -0,0 +1,79 @@
+//! Filtering lines by the time of their first timestamp, keeping context like `grep`.
+
+use std::collections::VecDeque;
+
+/// A line of text input with its number and ending.
+pub struct Line {
+    pub no: usize,
+    pub text: String,
+    pub ending: &'static str,
+}
+
+pub struct TimeFilter {
+    /// The inclusive start of the range in nanoseconds since the UNIX epoch.
+    since: Option<i64>,
+    /// The exclusive end of the range in nanoseconds since the UNIX epoch.
+    until: Option<i64>,
+    before: usize,
+    after: usize,
+    /// The latest lines outside the range, kept as context for a following line in it.
+    held: VecDeque<Line>,
+    /// The number of lines still to keep as context for the last line in the range.
+    after_left: usize,
+    /// The number of the last line kept.
+    last_kept: Option<usize>,
+}
+
+impl TimeFilter {
+    pub fn new(since: Option<i64>, until: Option<i64>, before: usize, after: usize) -> Self {
+        Self {
+            since,
+            until,
+            before,
+            after,
+            held: VecDeque::with_capacity(before),
+            after_left: 0,
+            last_kept: None,
+        }
+    }
+
+    fn contains(&self, time_ns: i64) -> bool {
+        self.since.is_none_or(|since| time_ns >= since)
+            && self.until.is_none_or(|until| time_ns < until)
+    }
+
+    /// Filters the next `line`, whose first timestamp is `time_ns`, calling `keep` with
+    /// each line to keep and whether it's separated from the previously kept one by
+    /// dropped lines.
+    pub fn push(
+        &mut self,
+        line: Line,
+        time_ns: Option<i64>,
+        mut keep: impl FnMut(Line, bool) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let has_context = self.before > 0 || self.after > 0;
+        let mut keep = |line: Line, last_kept: &mut Option<usize>| {
+            let is_separated = has_context && last_kept.is_some_and(|no| no + 1 < line.no);
+            *last_kept = Some(line.no);
+            keep(line, is_separated)
+        };
+        if time_ns.is_some_and(|time_ns| self.contains(time_ns)) {
+            for held in self.held.drain(..) {
+                keep(held, &mut self.last_kept)?;
+            }
+            self.after_left = self.after;
+            keep(line, &mut self.last_kept)
+        } else if self.after_left > 0 {
+            self.after_left -= 1;
+            keep(line, &mut self.last_kept)
+        } else {
+            if self.before > 0 {
+                if self.held.len() == self.before {
+                    self.held.pop_front();
+                }
+                self.held.push_back(line);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Trading-quarter and contract-month annotations.
+
+use chrono::{Datelike, NaiveDate};
+use clap::ValueEnum;
+
+/// Futures month codes, indexed by month - 1.
+const MONTH_CODES: [char; 12] = ['F', 'G', 'H', 'J', 'K', 'M', 'N', 'Q', 'U', 'V', 'X', 'Z'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Fiscal {
+    /// The fiscal quarter, e.g. `FY24 Q1`
+    Quarter,
+    /// The front contract month in the contract cycle, e.g. `H24`
+    Contract,
+}
+
+/// A fiscal year and futures contract calendar.
+#[derive(Clone, Debug)]
+pub struct Calendar {
+    /// The first month of the fiscal year, 1-12.
+    year_start: u32,
+    /// The listed contract months, 1-12, in ascending order.
+    cycle: Vec<u32>,
+}
+
+impl Calendar {
+    pub fn new(year_start: u32, cycle: &str) -> Self {
+        let mut cycle: Vec<u32> = cycle
+            .chars()
+            .filter_map(|c| MONTH_CODES.iter().position(|&code| code == c))
+            .map(|i| i as u32 + 1)
+            .collect();
+        cycle.sort_unstable();
+        cycle.dedup();
+        Self { year_start, cycle }
+    }
+
+    /// Returns the `fiscal` label of `date`.
+    pub fn label(&self, fiscal: Fiscal, date: NaiveDate) -> String {
+        match fiscal {
+            Fiscal::Quarter => {
+                let months_in = (date.month() + 12 - self.year_start) % 12;
+                // Fiscal years are named after the calendar year they end in
+                let year = if self.year_start > 1 && date.month() >= self.year_start {
+                    date.year() + 1
+                } else {
+                    date.year()
+                };
+                format!("FY{:02} Q{}", year.rem_euclid(100), months_in / 3 + 1)
+            }
+            Fiscal::Contract => {
+                let (month, year) = match self.cycle.iter().find(|&&m| m >= date.month()) {
+                    Some(&month) => (month, date.year()),
+                    // Roll to the first contract of the next year
+                    None => (
+                        self.cycle.first().copied().unwrap_or(date.month()),
+                        date.year() + 1,
+                    ),
+                };
+                format!(
+                    "{}{:02}",
+                    MONTH_CODES[month as usize - 1],
+                    year.rem_euclid(100)
+                )
+            }
+        }
+    }
+}
+
+/// Parses a contract cycle of futures month codes, e.g. `HMUZ`.
+pub fn parse_cycle(s: &str) -> Result<String, String> {
+    if !s.is_empty() && s.chars().all(|c| MONTH_CODES.contains(&c)) {
+        Ok(s.to_owned())
+    } else {
+        Err(format!(
+            "expected futures month codes from `{}`, found `{s}`",
+            MONTH_CODES.iter().collect::<String>()
+        ))
+    }
+}
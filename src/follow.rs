@@ -0,0 +1,96 @@
+//! Reading a file as it grows, like `tail -F`.
+
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+/// How long to wait for more data at the end of the file.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads a file without ever reaching its end, waiting for more data instead. When the
+/// file is truncated or replaced, as by `logrotate`, it's read again from the start.
+pub struct Follow {
+    path: PathBuf,
+    file: File,
+    /// The number of bytes read from `file`.
+    position: u64,
+    id: Option<FileId>,
+}
+
+/// Identifies a file independently of its path.
+#[derive(PartialEq, Eq)]
+struct FileId {
+    device: u64,
+    inode: u64,
+}
+
+impl Follow {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let file = File::open(&path)?;
+        let id = file_id(&file.metadata()?);
+        Ok(Self {
+            path,
+            file,
+            position: 0,
+            id,
+        })
+    }
+
+    /// Starts reading the file at `path` over if it's no longer the one being read or
+    /// has been truncated, returning whether it did.
+    fn reopen_if_rotated(&mut self) -> io::Result<bool> {
+        // The file may be missing between being moved away and recreated
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(false);
+        };
+        let id = file_id(&metadata);
+        if id != self.id {
+            let Ok(file) = File::open(&self.path) else {
+                return Ok(false);
+            };
+            self.file = file;
+            self.id = id;
+        } else if metadata.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+        } else {
+            return Ok(false);
+        }
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                self.position += read as u64;
+                return Ok(read);
+            }
+            if !self.reopen_if_rotated()? {
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(FileId {
+        device: metadata.dev(),
+        inode: metadata.ino(),
+    })
+}
+
+/// Replaced files can only be detected by truncation without inodes.
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
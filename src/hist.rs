@@ -0,0 +1,87 @@
+//! Histograms of the times of detected timestamps for `epoch hist`.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+};
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+
+use crate::{duration, open_input, output, Args, Reformatter};
+
+/// The most buckets to print, which a stray timestamp decades away could otherwise
+/// multiply.
+const MAX_BUCKETS: i64 = 100_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum HistFormat {
+    /// A bar chart of the counts
+    #[default]
+    Chart,
+    /// CSV with a header and a `bucket,count` row per bucket
+    Csv,
+}
+
+/// Counts the timestamps detected in the input in buckets of `bucket_ns` and writes each
+/// bucket from the first to the last, including empty ones, with bars of at most `width`
+/// characters.
+pub fn hist(args: &Args, bucket_ns: i64, format: HistFormat, width: usize) -> anyhow::Result<()> {
+    if bucket_ns <= 0 {
+        bail!("expected a positive bucket duration");
+    }
+    let reformatter = Reformatter::new(args)?;
+    let mut counts = BTreeMap::<i64, usize>::new();
+    for (i, line) in open_input(args)?.split(b'\n').enumerate() {
+        let line = line.context("failed to read the input")?;
+        let line = String::from_utf8_lossy(&line);
+        reformatter.start_line(i + 1);
+        for m in reformatter.matches(&line) {
+            *counts.entry(m.time_ns.div_euclid(bucket_ns)).or_default() += 1;
+        }
+    }
+
+    let mut output = output::open(args)?;
+    let (Some((&first, _)), Some((&last, _))) = (counts.first_key_value(), counts.last_key_value())
+    else {
+        if format == HistFormat::Csv {
+            writeln!(output, "bucket,count")?;
+        }
+        return output.finish();
+    };
+    if last - first >= MAX_BUCKETS {
+        bail!(
+            "the timestamps span more than {MAX_BUCKETS} buckets, try a longer `--bucket` or \
+             narrowing them with `--min-date` and `--max-date`"
+        );
+    }
+    let precision = duration::precision(bucket_ns);
+    let max_count = counts.values().copied().max().unwrap_or_default();
+    let count_width = max_count.to_string().len();
+    if format == HistFormat::Csv {
+        writeln!(output, "bucket,count")?;
+    }
+    for bucket in first..=last {
+        let count = counts.get(&bucket).copied().unwrap_or_default();
+        let label = reformatter.format_time(bucket.saturating_mul(bucket_ns), precision);
+        match format {
+            HistFormat::Chart => {
+                // Round up so every non-empty bucket has a bar
+                let bar = (count * width).div_ceil(max_count);
+                let row = format!("{label}  {count:>count_width$}  {}", "#".repeat(bar));
+                writeln!(output, "{}", row.trim_end())?;
+            }
+            HistFormat::Csv => writeln!(output, "{},{count}", csv_field(&label))?,
+        }
+    }
+    output.finish()
+}
+
+/// Quotes `field` if it has a comma, like RFC 2822 dates, or a quote.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
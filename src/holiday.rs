@@ -0,0 +1,52 @@
+//! Weekend and holiday annotations.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate, Weekday};
+
+/// Days markets are closed: weekends and any listed holidays.
+#[derive(Clone, Debug, Default)]
+pub struct Holidays {
+    /// The name of each holiday, which may be empty.
+    names: HashMap<NaiveDate, String>,
+}
+
+impl Holidays {
+    /// Loads holidays from a file with a `YYYY-MM-DD` date and an optional name per line.
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut names = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (date, name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").with_context(|| {
+                format!(
+                    "expected a `YYYY-MM-DD` date at the start of line {} of {}, found `{date}`",
+                    i + 1,
+                    path.display()
+                )
+            })?;
+            names.insert(date, name.trim().to_owned());
+        }
+        Ok(Self { names })
+    }
+
+    /// Returns `holiday` with its name if `date` is a listed holiday, `weekend` if it's on
+    /// a weekend, or `None` for other days.
+    pub fn label(&self, date: NaiveDate) -> Option<String> {
+        match self.names.get(&date) {
+            Some(name) if name.is_empty() => Some("holiday".to_owned()),
+            Some(name) => Some(format!("holiday: {name}")),
+            None if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) => {
+                Some("weekend".to_owned())
+            }
+            None => None,
+        }
+    }
+}
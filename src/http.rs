@@ -0,0 +1,47 @@
+//! Streaming input from HTTP and HTTPS URLs.
+
+use std::io::{BufRead, BufReader};
+
+use anyhow::Context;
+
+/// Returns `true` if `input` is a URL rather than a path.
+pub fn is_url(input: &str) -> bool {
+    #[cfg(feature = "cloud")]
+    if crate::cloud::is_object_url(input) {
+        return true;
+    }
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// Requests `url` with the given `Name: value` headers and returns a reader over the
+/// response body.
+pub fn open(url: &str, headers: &[String]) -> anyhow::Result<Box<dyn BufRead>> {
+    #[cfg(feature = "cloud")]
+    if crate::cloud::is_object_url(url) {
+        let (https_url, object_headers) = crate::cloud::request(url)?;
+        let mut request = ureq::get(&https_url);
+        for (name, value) in object_headers {
+            request = request.header(name, value);
+        }
+        return crate::cloud::decompress(read_response(url, request, headers)?);
+    }
+    read_response(url, ureq::get(url), headers)
+}
+
+/// Sends `request` for `url` with the additional `Name: value` headers.
+fn read_response(
+    url: &str,
+    mut request: ureq::RequestBuilder<ureq::typestate::WithoutBody>,
+    headers: &[String],
+) -> anyhow::Result<Box<dyn BufRead>> {
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .with_context(|| format!("expected `NAME: VALUE` header, found `{header}`"))?;
+        request = request.header(name.trim(), value.trim());
+    }
+    let response = request
+        .call()
+        .with_context(|| format!("failed to request {url}"))?;
+    Ok(Box::new(BufReader::new(response.into_body().into_reader())))
+}
@@ -0,0 +1,171 @@
+//! Detection of IDs with embedded creation times, like version 7 UUIDs and ULIDs.
+
+use std::{ops::Range, sync::LazyLock};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use epoch_to::Precision;
+use regex::Regex;
+
+/// Version 1 and 7 UUIDs, which embed their creation time.
+static UUID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b([0-9a-fA-F]{8})-([0-9a-fA-F]{4})-([17])([0-9a-fA-F]{3})-[89abAB][0-9a-fA-F]{3}-[0-9a-fA-F]{12}\b",
+    )
+    .unwrap()
+});
+
+/// 100-nanosecond intervals from the start of the Gregorian calendar, 1582-10-15, to the
+/// UNIX epoch.
+const GREGORIAN_TO_UNIX: i64 = 0x01B2_1DD2_1381_4000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IdMode {
+    /// Keep the ID and append its creation time
+    Annotate,
+    /// Replace the ID with its creation time
+    Replace,
+}
+
+/// A detected ID's range and creation time.
+pub type Id = (Range<usize>, DateTime<Utc>, Precision);
+
+/// Returns an iterator over the version 1 and 7 UUIDs in `line`.
+pub fn uuids(line: &str) -> impl Iterator<Item = Id> + '_ {
+    UUID.captures_iter(line).filter_map(|caps| {
+        let hex = |i: usize| i64::from_str_radix(&caps[i], 16).ok();
+        let (time, precision) = if &caps[3] == "7" {
+            // The first 48 bits are milliseconds since the UNIX epoch
+            let millis = (hex(1)? << 16) | hex(2)?;
+            (DateTime::from_timestamp_millis(millis)?, Precision::Millis)
+        } else {
+            // A 60-bit count of 100-nanosecond intervals split into low, middle, and high
+            // fields in that order
+            let intervals = (hex(4)? << 48) | (hex(2)? << 32) | hex(1)?;
+            let nanos = (intervals - GREGORIAN_TO_UNIX).checked_mul(100)?;
+            (DateTime::from_timestamp_nanos(nanos), Precision::Nanos)
+        };
+        Some((caps.get(0).unwrap().range(), time, precision))
+    })
+}
+
+/// Decimal runs as long as 64-bit Snowflake IDs from the last few decades.
+static SNOWFLAKE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d{15,19}\b").unwrap());
+
+/// Finds Snowflake IDs, whose bits above the lowest 22 are milliseconds since a custom
+/// epoch.
+#[derive(Clone, Debug)]
+pub struct Snowflakes {
+    /// Milliseconds since the UNIX epoch of the zero point of IDs.
+    pub epoch_ms: i64,
+    /// Seconds since the UNIX epoch that creation times must fall in, which keeps other
+    /// long numbers from being detected.
+    pub window: Range<i64>,
+}
+
+impl Snowflakes {
+    /// Returns an iterator over the Snowflake IDs in `line`.
+    pub fn find<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Id> + 'a {
+        SNOWFLAKE.find_iter(line).filter_map(|m| {
+            let id: i64 = m.as_str().parse().ok()?;
+            let millis = (id >> 22).checked_add(self.epoch_ms)?;
+            let time = DateTime::from_timestamp_millis(millis)
+                .filter(|time| self.window.contains(&time.timestamp()))?;
+            Some((m.range(), time, Precision::Millis))
+        })
+    }
+}
+
+/// Parses the epoch of Snowflake IDs: `twitter`, `discord`, or milliseconds since the
+/// UNIX epoch.
+pub fn parse_snowflake_epoch(s: &str) -> Result<i64, String> {
+    match s {
+        "twitter" => Ok(1_288_834_974_657),
+        "discord" => Ok(1_420_070_400_000),
+        _ => s.parse().map_err(|_| {
+            format!(
+                "expected `twitter`, `discord`, or milliseconds since the UNIX epoch, found `{s}`"
+            )
+        }),
+    }
+}
+
+/// Runs of 24 hex digits like MongoDB ObjectIds.
+static OBJECT_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[0-9a-fA-F]{24}\b").unwrap());
+
+/// Returns an iterator over the MongoDB ObjectIds in `line` created within `window`, in
+/// seconds since the UNIX epoch. Their first 4 bytes are seconds since the UNIX epoch.
+pub fn object_ids(line: &str, window: Range<i64>) -> impl Iterator<Item = Id> + '_ {
+    OBJECT_ID.find_iter(line).filter_map(move |m| {
+        let seconds = i64::from_str_radix(&m.as_str()[..8], 16).ok()?;
+        let time = DateTime::from_timestamp(seconds, 0)
+            .filter(|time| window.contains(&time.timestamp()))?;
+        Some((m.range(), time, Precision::Seconds))
+    })
+}
+
+/// ULIDs: 26 Crockford base32 digits, the first 10 of which are milliseconds since the
+/// UNIX epoch.
+static ULID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[0-7][0-9A-HJKMNP-TV-Za-hjkmnp-tv-z]{25}\b").unwrap());
+
+/// KSUIDs: 27 base62 digits encoding 20 bytes, the first 4 of which are seconds since
+/// 2014-05-13T16:53:20Z.
+static KSUID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[0-9A-Za-z]{27}\b").unwrap());
+
+const KSUID_EPOCH: i64 = 1_400_000_000;
+
+/// Returns an iterator over the ULIDs in `line` created within `window`, in seconds
+/// since the UNIX epoch.
+pub fn ulids(line: &str, window: Range<i64>) -> impl Iterator<Item = Id> + '_ {
+    ULID.find_iter(line).filter_map(move |m| {
+        let millis = m.as_str()[..10]
+            .bytes()
+            .try_fold(0i64, |millis, b| Some((millis << 5) | crockford_digit(b)?))?;
+        let time = DateTime::from_timestamp_millis(millis)
+            .filter(|time| window.contains(&time.timestamp()))?;
+        Some((m.range(), time, Precision::Millis))
+    })
+}
+
+/// Returns an iterator over the KSUIDs in `line` created within `window`, in seconds
+/// since the UNIX epoch.
+pub fn ksuids(line: &str, window: Range<i64>) -> impl Iterator<Item = Id> + '_ {
+    KSUID.find_iter(line).filter_map(move |m| {
+        // Decode the big-endian 160-bit number one base62 digit at a time
+        let mut bytes = [0u8; 20];
+        for b in m.as_str().bytes() {
+            let mut carry = u32::from(base62_digit(b)?);
+            for byte in bytes.iter_mut().rev() {
+                let value = u32::from(*byte) * 62 + carry;
+                *byte = value as u8;
+                carry = value >> 8;
+            }
+            if carry != 0 {
+                return None;
+            }
+        }
+        let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let time = DateTime::from_timestamp(KSUID_EPOCH + i64::from(seconds), 0)
+            .filter(|time| window.contains(&time.timestamp()))?;
+        Some((m.range(), time, Precision::Seconds))
+    })
+}
+
+/// Returns the value of a Crockford base32 digit, which skips `I`, `L`, `O`, and `U`.
+fn crockford_digit(b: u8) -> Option<i64> {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    ALPHABET
+        .iter()
+        .position(|&digit| digit == b.to_ascii_uppercase())
+        .map(|i| i as i64)
+}
+
+fn base62_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'Z' => Some(b - b'A' + 10),
+        b'a'..=b'z' => Some(b - b'a' + 36),
+        _ => None,
+    }
+}
@@ -0,0 +1,30 @@
+//! Detection of existing RFC 3339 timestamps like `2024-02-28T20:43:09.456789123+01:00`.
+
+use std::{ops::Range, sync::LazyLock};
+
+use chrono::{DateTime, Utc};
+use epoch_to::Precision;
+use regex::Regex;
+
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b\d{4}-\d\d-\d\d[Tt]\d\d:\d\d:\d\d(?:\.(\d{1,9}))?(?:[Zz]|[+-]\d\d:\d\d)")
+        .unwrap()
+});
+
+/// Returns an iterator over the RFC 3339 timestamps in `line` along with the precision
+/// of their fractional seconds.
+pub fn timestamps(
+    line: &str,
+) -> impl Iterator<Item = (Range<usize>, DateTime<Utc>, Precision)> + '_ {
+    TIMESTAMP.captures_iter(line).filter_map(|caps| {
+        let m = caps.get(0).unwrap();
+        let time = DateTime::parse_from_rfc3339(m.as_str()).ok()?;
+        let precision = match caps.get(1).map_or(0, |fraction| fraction.len()) {
+            0 => Precision::Seconds,
+            1..=3 => Precision::Millis,
+            4..=6 => Precision::Micros,
+            _ => Precision::Nanos,
+        };
+        Some((m.range(), time.to_utc(), precision))
+    })
+}
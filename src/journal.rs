@@ -0,0 +1,78 @@
+//! Reading the systemd journal for `epoch journal`.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context};
+use serde_json::{Map, Value};
+
+use crate::{output, Args, Precision, Reformatter};
+
+/// Fields of journal entries with microseconds since the UNIX epoch.
+const TIME_FIELDS: [&str; 2] = ["__REALTIME_TIMESTAMP", "_SOURCE_REALTIME_TIMESTAMP"];
+
+/// Writes the journal entries of `units`, or all of them if it's empty, as JSON lines with
+/// their times and the timestamps in their messages converted. Keeps writing new entries
+/// with `follow`.
+pub fn journal(args: &Args, units: &[String], follow: bool) -> anyhow::Result<()> {
+    let reformatter = Reformatter::new(args)?;
+    let mut journalctl = Command::new("journalctl");
+    journalctl.args(["--output", "json", "--no-pager"]);
+    for unit in units {
+        journalctl.args(["--unit", unit]);
+    }
+    if follow {
+        journalctl.arg("--follow");
+    }
+    let mut child = journalctl
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run journalctl")?;
+    let entries = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+    // A followed journal never finishes, so it's written in place
+    let mut output = output::open(&Args {
+        no_atomic: args.no_atomic || follow,
+        ..args.clone()
+    })?;
+    for (i, entry) in entries.lines().enumerate() {
+        let entry = entry.context("failed to read from journalctl")?;
+        let entry: Map<String, Value> = serde_json::from_str(&entry)
+            .with_context(|| format!("failed to parse journal entry {}", i + 1))?;
+        reformatter.start_line(i + 1);
+        serde_json::to_writer(&mut output, &convert_entry(&reformatter, entry)?)?;
+        output.write_all(b"\n")?;
+        if follow {
+            output.flush()?;
+        }
+    }
+    let status = child.wait().context("failed to run journalctl")?;
+    if !status.success() {
+        bail!("journalctl failed with {status}");
+    }
+    output.finish()
+}
+
+/// Converts the times of `entry` and the timestamps in its message.
+fn convert_entry(
+    reformatter: &Reformatter,
+    mut entry: Map<String, Value>,
+) -> anyhow::Result<Map<String, Value>> {
+    for field in TIME_FIELDS {
+        let micros = entry
+            .get(field)
+            .and_then(Value::as_str)
+            .and_then(|micros| micros.parse::<i64>().ok());
+        if let Some(time_ns) = micros.and_then(|micros| micros.checked_mul(1_000)) {
+            let formatted = reformatter.format_time(time_ns, Precision::Micros);
+            entry.insert(field.to_owned(), Value::String(formatted));
+        }
+    }
+    // Messages that aren't valid UTF-8 are arrays of bytes, which are kept as is
+    if let Some(Value::String(message)) = entry.get_mut("MESSAGE") {
+        *message = reformatter.convert(message)?;
+    }
+    Ok(entry)
+}
@@ -0,0 +1,178 @@
+//! Consuming Kafka topics for `epoch kafka`.
+
+use std::{io::Write, time::Duration};
+
+use anyhow::{bail, Context};
+use rskafka::{
+    client::{
+        partition::{OffsetAt, PartitionClient, UnknownTopicHandling},
+        ClientBuilder,
+    },
+    BackoffConfig,
+};
+
+use crate::{output, Args, Precision, Reformatter};
+
+/// The most bytes of records to fetch from a partition at once.
+const MAX_FETCH_BYTES: i32 = 1 << 20;
+/// How long brokers wait for new records before answering a fetch with none.
+const MAX_WAIT_MS: i32 = 100;
+/// How long to keep retrying a request to unreachable brokers before giving up.
+const DEADLINE: Duration = Duration::from_secs(30);
+
+/// Writes the messages of every partition of `topic` as they're produced, or from the
+/// oldest one kept with `from_beginning`, with the timestamps in their payloads converted.
+/// Each is prefixed with the time the broker assigned it with `message_time`.
+pub fn kafka(
+    args: &Args,
+    brokers: &[String],
+    topic: &str,
+    from_beginning: bool,
+    message_time: bool,
+) -> anyhow::Result<()> {
+    let reformatter = Reformatter::new(&payload_args(args))?;
+    // Consuming never finishes, so the output is written in place
+    let mut output = output::open(&Args {
+        no_atomic: true,
+        ..args.clone()
+    })?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let client = ClientBuilder::new(brokers.to_vec())
+            .backoff_config(BackoffConfig {
+                max_backoff: Duration::from_secs(5),
+                deadline: Some(DEADLINE),
+                ..BackoffConfig::default()
+            })
+            .build()
+            .await
+            .with_context(|| format!("failed to connect to {}", brokers.join(",")))?;
+        let topics = client.list_topics().await?;
+        let Some(partitions) = topics
+            .into_iter()
+            .find(|t| t.name == topic)
+            .map(|t| t.partitions)
+        else {
+            bail!("no topic `{topic}` on {}", brokers.join(","));
+        };
+        let at = if from_beginning {
+            OffsetAt::Earliest
+        } else {
+            OffsetAt::Latest
+        };
+        let mut consumers: Vec<(PartitionClient, i64)> = Vec::new();
+        for partition in partitions {
+            let partition = client
+                .partition_client(topic, partition, UnknownTopicHandling::Error)
+                .await?;
+            let offset = partition.get_offset(at).await?;
+            consumers.push((partition, offset));
+        }
+
+        let mut message_no = 0;
+        loop {
+            for (partition, offset) in &mut consumers {
+                let (records, _) = partition
+                    .fetch_records(*offset, 1..MAX_FETCH_BYTES, MAX_WAIT_MS)
+                    .await
+                    .with_context(|| {
+                        format!("failed to fetch from partition {}", partition.partition())
+                    })?;
+                for record in records {
+                    *offset = record.offset + 1;
+                    message_no += 1;
+                    reformatter.start_line(message_no);
+                    let time_ms = message_time.then(|| record.record.timestamp.timestamp_millis());
+                    let payload = record.record.value.unwrap_or_default();
+                    match format_message(&reformatter, &payload, time_ms) {
+                        Ok(line) => writeln!(output, "{line}")?,
+                        Err(e) => eprintln!(
+                            "warning: skipping message {} of partition {}: {e:#}",
+                            record.offset,
+                            partition.partition()
+                        ),
+                    }
+                }
+            }
+            output.flush()?;
+        }
+    })
+}
+
+/// Returns `args` for converting payloads, which are usually structured, so `--keys`
+/// selects their fields without `--kv`.
+fn payload_args(args: &Args) -> Args {
+    Args {
+        kv: args.kv || !args.keys.is_empty(),
+        ..args.clone()
+    }
+}
+
+/// Returns `payload` with its timestamps converted, prefixed with the message's time if
+/// given, in milliseconds since the UNIX epoch.
+fn format_message(
+    reformatter: &Reformatter,
+    payload: &[u8],
+    time_ms: Option<i64>,
+) -> anyhow::Result<String> {
+    let payload = reformatter.convert(&String::from_utf8_lossy(payload))?;
+    let Some(time_ms) = time_ms else {
+        return Ok(payload);
+    };
+    let time_ns = time_ms
+        .checked_mul(1_000_000)
+        .with_context(|| format!("the message time of {time_ms}ms is out of range"))?;
+    let time = reformatter.format_time(time_ns, Precision::Millis);
+    Ok(format!("{time} {payload}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn reformatter(args: &[&str]) -> Reformatter {
+        let args = Args::parse_from(["epoch"].iter().chain(args));
+        Reformatter::new(&payload_args(&args)).unwrap()
+    }
+
+    #[test]
+    fn test_format_message() {
+        let reformatter = reformatter(&[]);
+        let payload = br#"{"ts": 1709152989456, "qty": 5}"#;
+        assert_eq!(
+            format_message(&reformatter, payload, None).unwrap(),
+            r#"{"ts": 2024-02-28T20:43:09.456Z, "qty": 5}"#
+        );
+        assert_eq!(
+            format_message(&reformatter, payload, Some(1709152990000)).unwrap(),
+            r#"2024-02-28T20:43:10.000Z {"ts": 2024-02-28T20:43:09.456Z, "qty": 5}"#
+        );
+    }
+
+    #[test]
+    fn test_format_message_keys() {
+        let payload = br#"{"ts": 1709152989456, "id": 1709152989457}"#;
+        assert_eq!(
+            format_message(&reformatter(&["--keys", "ts"]), payload, None).unwrap(),
+            r#"{"ts": 2024-02-28T20:43:09.456Z, "id": 1709152989457}"#
+        );
+    }
+
+    #[test]
+    fn test_format_message_invalid_utf8() {
+        assert_eq!(
+            format_message(&reformatter(&[]), b"\xff 1709152989", None).unwrap(),
+            "\u{fffd} 2024-02-28T20:43:09Z"
+        );
+    }
+
+    #[test]
+    fn test_format_message_time_out_of_range() {
+        let error = format_message(&reformatter(&[]), b"x", Some(i64::MAX)).unwrap_err();
+        assert!(error.to_string().contains("out of range"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Conversion of timestamps in the TAI and GPS time scales to UTC.
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+/// Seconds from the NTP epoch, 1900-01-01, to the UNIX epoch.
+const NTP_TO_UNIX: i64 = 2_208_988_800;
+
+/// Seconds since the UNIX epoch at which TAI - UTC changed, with its new value in seconds.
+const LEAP_SECONDS: [(i64, i64); 28] = [
+    (63_072_000, 10),    // 1972-01-01
+    (78_796_800, 11),    // 1972-07-01
+    (94_694_400, 12),    // 1973-01-01
+    (126_230_400, 13),   // 1974-01-01
+    (157_766_400, 14),   // 1975-01-01
+    (189_302_400, 15),   // 1976-01-01
+    (220_924_800, 16),   // 1977-01-01
+    (252_460_800, 17),   // 1978-01-01
+    (283_996_800, 18),   // 1979-01-01
+    (315_532_800, 19),   // 1980-01-01
+    (362_793_600, 20),   // 1981-07-01
+    (394_329_600, 21),   // 1982-07-01
+    (425_865_600, 22),   // 1983-07-01
+    (489_024_000, 23),   // 1985-07-01
+    (567_993_600, 24),   // 1988-01-01
+    (631_152_000, 25),   // 1990-01-01
+    (662_688_000, 26),   // 1991-01-01
+    (709_948_800, 27),   // 1992-07-01
+    (741_484_800, 28),   // 1993-07-01
+    (773_020_800, 29),   // 1994-07-01
+    (820_454_400, 30),   // 1996-01-01
+    (867_715_200, 31),   // 1997-07-01
+    (915_148_800, 32),   // 1999-01-01
+    (1_136_073_600, 33), // 2006-01-01
+    (1_230_768_000, 34), // 2009-01-01
+    (1_341_100_800, 35), // 2012-07-01
+    (1_435_708_800, 36), // 2015-07-01
+    (1_483_228_800, 37), // 2017-01-01
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Scale {
+    /// Coordinated Universal Time, which skips leap seconds like UNIX time
+    Utc,
+    /// International Atomic Time, which counts leap seconds
+    Tai,
+    /// GPS time, which counts leap seconds since 1980 and is 19 seconds behind TAI
+    Gps,
+}
+
+/// Converts timestamps in a time scale that counts leap seconds to UTC.
+#[derive(Clone, Debug)]
+pub struct TimeScale {
+    scale: Scale,
+    /// Seconds since the UNIX epoch at which TAI - UTC changed, with its new value in
+    /// seconds, in ascending order.
+    leap_seconds: Vec<(i64, i64)>,
+}
+
+impl TimeScale {
+    /// Returns a converter from `scale` with the bundled leap-second table, which ends
+    /// with the leap second of 2016-12-31.
+    pub fn new(scale: Scale) -> Self {
+        Self {
+            scale,
+            leap_seconds: LEAP_SECONDS.to_vec(),
+        }
+    }
+
+    /// Returns a converter from `scale` with the leap-second table at `path` in the IETF
+    /// `leap-seconds.list` format: NTP seconds and TAI - UTC per line, with `#` comments.
+    pub fn load(scale: Scale, path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut leap_seconds = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line);
+            let mut fields = line.split_whitespace();
+            let Some(ntp_seconds) = fields.next() else {
+                continue;
+            };
+            let entry = ntp_seconds
+                .parse::<i64>()
+                .ok()
+                .zip(fields.next().and_then(|offset| offset.parse::<i64>().ok()));
+            let (ntp_seconds, offset) = entry.with_context(|| {
+                format!(
+                    "expected NTP seconds and TAI - UTC on line {} of {}",
+                    i + 1,
+                    path.display()
+                )
+            })?;
+            leap_seconds.push((ntp_seconds - NTP_TO_UNIX, offset));
+        }
+        leap_seconds.sort_unstable();
+        Ok(Self {
+            scale,
+            leap_seconds,
+        })
+    }
+
+    /// Converts nanoseconds since the epoch in this time scale to UTC.
+    pub fn to_utc(&self, time_ns: i64) -> i64 {
+        // The offset of this scale from UTC, in seconds
+        let offset = |tai_minus_utc: i64| match self.scale {
+            Scale::Utc => 0,
+            Scale::Tai => tai_minus_utc,
+            Scale::Gps => tai_minus_utc - 19,
+        };
+        let time_s = time_ns.div_euclid(1_000_000_000);
+        // Leap seconds take effect at their UTC time plus the new offset in this scale
+        let offset_s = self
+            .leap_seconds
+            .iter()
+            .rev()
+            .find(|&&(utc_s, tai_minus_utc)| time_s >= utc_s + offset(tai_minus_utc))
+            .map_or(0, |&(_, tai_minus_utc)| offset(tai_minus_utc));
+        time_ns.saturating_sub(offset_s * 1_000_000_000)
+    }
+}
@@ -0,0 +1,410 @@
+//! Detection and formatting of epoch timestamps in text.
+//!
+//! Without the default `cli` feature, the library only depends on `alloc`, so it can be
+//! used in `no_std` environments. Without a clock, the range of dates to detect is always
+//! given explicitly.
+
+#![cfg_attr(not(feature = "cli"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use core::ops::{Range, RangeInclusive};
+
+use chrono::DateTime;
+
+/// The precision a timestamp is rendered with, ordered from least to most precise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precision {
+    /// Only the date.
+    Days,
+    /// Whole seconds.
+    Seconds,
+    /// Three fractional digits.
+    Millis,
+    /// Six fractional digits.
+    Micros,
+    /// Nine fractional digits.
+    Nanos,
+}
+
+impl Precision {
+    /// Returns the `strftime` format of RFC 3339 timestamps with this precision, either
+    /// with an offset or in UTC.
+    pub const fn rfc_format<const LOCALIZE: bool>(self) -> &'static str {
+        match (LOCALIZE, self) {
+            (_, Precision::Days) => "%Y-%m-%d",
+            (true, Precision::Seconds) => "%Y-%m-%dT%H:%M:%S%Z",
+            (false, Precision::Seconds) => "%Y-%m-%dT%H:%M:%SZ",
+            (true, Precision::Millis) => "%Y-%m-%dT%H:%M:%S%.3f%Z",
+            (false, Precision::Millis) => "%Y-%m-%dT%H:%M:%S%.3fZ",
+            (true, Precision::Micros) => "%Y-%m-%dT%H:%M:%S%.6f%Z",
+            (false, Precision::Micros) => "%Y-%m-%dT%H:%M:%S%.6fZ",
+            (true, Precision::Nanos) => "%Y-%m-%dT%H:%M:%S%.9f%Z",
+            (false, Precision::Nanos) => "%Y-%m-%dT%H:%M:%S%.9fZ",
+        }
+    }
+}
+
+/// The resolution of an epoch timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Unit {
+    /// Seconds
+    #[cfg_attr(feature = "cli", value(name = "s"))]
+    Seconds,
+    /// Milliseconds
+    #[cfg_attr(feature = "cli", value(name = "ms"))]
+    Millis,
+    /// Microseconds
+    #[cfg_attr(feature = "cli", value(name = "us"))]
+    Micros,
+    /// Nanoseconds
+    #[cfg_attr(feature = "cli", value(name = "ns"))]
+    Nanos,
+    /// Windows `FILETIME`: 100-nanosecond intervals since 1601-01-01.
+    #[cfg_attr(feature = "cli", value(name = "filetime"))]
+    FileTime,
+    /// .NET `DateTime.Ticks`: 100-nanosecond intervals since 0001-01-01.
+    #[cfg_attr(feature = "cli", value(name = "dotnet-ticks"))]
+    DotNetTicks,
+    /// Days, like Arrow `DATE32` values
+    #[cfg_attr(feature = "cli", value(name = "days"))]
+    Days,
+    /// Hours
+    #[cfg_attr(feature = "cli", value(name = "hours"))]
+    Hours,
+    /// Minutes
+    #[cfg_attr(feature = "cli", value(name = "minutes"))]
+    Minutes,
+}
+
+impl Unit {
+    /// Units detected by default, in order of precedence.
+    pub const DETECTED: [Unit; 3] = [Unit::Seconds, Unit::Millis, Unit::Nanos];
+
+    /// Units longer than a second, which are only detected when requested.
+    pub const COARSE: [Unit; 3] = [Unit::Days, Unit::Hours, Unit::Minutes];
+
+    /// The number of this unit in a second, which is zero for units longer than a
+    /// second.
+    pub const fn per_second(self) -> i64 {
+        1_000_000_000 / self.nanos()
+    }
+
+    /// The number of nanoseconds in this unit.
+    pub const fn nanos(self) -> i64 {
+        match self {
+            Unit::Days => 86_400_000_000_000,
+            Unit::Hours => 3_600_000_000_000,
+            Unit::Minutes => 60_000_000_000,
+            Unit::Seconds => 1_000_000_000,
+            Unit::Millis => 1_000_000,
+            Unit::Micros => 1_000,
+            Unit::Nanos => 1,
+            Unit::FileTime | Unit::DotNetTicks => 100,
+        }
+    }
+
+    /// The value of the UNIX epoch in this unit, where `base_s` is the epoch of units
+    /// without an inherent one, in seconds since the UNIX epoch. Bases that aren't a
+    /// whole number of a unit longer than a second are rounded down.
+    pub const fn unix_epoch(self, base_s: i64) -> i64 {
+        match self {
+            Unit::FileTime => 116_444_736_000_000_000,
+            Unit::DotNetTicks => 621_355_968_000_000_000,
+            _ => saturate((base_s as i128 * 1_000_000_000).div_euclid(self.nanos() as i128))
+                .saturating_neg(),
+        }
+    }
+
+    /// Whether this unit counts from the configurable epoch base rather than an inherent
+    /// epoch.
+    pub const fn is_epoch_relative(self) -> bool {
+        !matches!(self, Unit::FileTime | Unit::DotNetTicks)
+    }
+
+    /// Converts seconds since the UNIX epoch to a value in this unit, rounding down.
+    pub fn unix_seconds_to_unit(self, s: i64, base_s: i64) -> i64 {
+        saturate((s as i128 * 1_000_000_000).div_euclid(self.nanos() as i128))
+            .saturating_add(self.unix_epoch(base_s))
+    }
+
+    /// Converts `n` in this unit to nanoseconds since the UNIX epoch.
+    pub fn to_nanos(self, n: i64, base_s: i64) -> Option<i64> {
+        n.checked_sub(self.unix_epoch(base_s))?
+            .checked_mul(self.nanos())
+    }
+
+    /// Converts nanoseconds since the UNIX epoch to a value in this unit, rounding down.
+    pub fn nanos_to_unit(self, time_ns: i64, base_s: i64) -> Option<i64> {
+        time_ns
+            .div_euclid(self.nanos())
+            .checked_add(self.unix_epoch(base_s))
+    }
+
+    /// Returns the precision timestamps in this unit are rendered with, where units
+    /// coarser than seconds but finer than days have whole seconds.
+    pub const fn precision(self) -> Precision {
+        match self {
+            Unit::Days => Precision::Days,
+            Unit::Hours | Unit::Minutes | Unit::Seconds => Precision::Seconds,
+            Unit::Millis => Precision::Millis,
+            Unit::Micros => Precision::Micros,
+            Unit::Nanos | Unit::FileTime | Unit::DotNetTicks => Precision::Nanos,
+        }
+    }
+}
+
+/// A timestamp detected within a line of text.
+#[derive(Debug)]
+pub struct Match {
+    /// Byte range of the original number within the line.
+    pub range: Range<usize>,
+    /// Nanoseconds since the UNIX epoch.
+    pub time_ns: i64,
+    /// The precision of the unit the number was detected in.
+    pub precision: Precision,
+}
+
+/// A number in a line of text that may be a timestamp.
+#[derive(Debug)]
+pub struct Candidate {
+    /// Byte range of the number within the line, including any sign.
+    pub range: Range<usize>,
+    /// Whether the number has a fractional part like `1709152989.456`.
+    pub is_fraction: bool,
+}
+
+/// Detects integer epoch timestamps within a range of dates.
+#[derive(Debug)]
+pub struct Detector {
+    bounds: alloc::vec::Vec<(Unit, Range<i64>)>,
+    /// Seconds since the UNIX epoch of the zero point of seconds, milliseconds, and
+    /// nanoseconds.
+    epoch_base: i64,
+    allow_negative: bool,
+    word_boundary: bool,
+}
+
+impl Detector {
+    /// Creates a detector for timestamps in any of `units` from `lower_s` up to `upper_s`
+    /// seconds since the UNIX epoch. Units earlier in `units` take precedence.
+    pub fn new(units: impl IntoIterator<Item = Unit>, lower_s: i64, upper_s: i64) -> Self {
+        Self::with_epoch_base(units, lower_s, upper_s, 0)
+    }
+
+    /// Creates a detector like [`Detector::new`] where seconds, milliseconds, and
+    /// nanoseconds count from `epoch_base` seconds since the UNIX epoch.
+    pub fn with_epoch_base(
+        units: impl IntoIterator<Item = Unit>,
+        lower_s: i64,
+        upper_s: i64,
+        epoch_base: i64,
+    ) -> Self {
+        let bounds = units
+            .into_iter()
+            .map(|unit| {
+                (
+                    unit,
+                    unit.unix_seconds_to_unit(lower_s, epoch_base)
+                        ..unit.unix_seconds_to_unit(upper_s, epoch_base),
+                )
+            })
+            .collect();
+        Self {
+            bounds,
+            epoch_base,
+            allow_negative: false,
+            word_boundary: false,
+        }
+    }
+
+    /// Also detects negative timestamps before the epoch, with the bounds mirrored around
+    /// it.
+    pub fn allow_negative(mut self, allow_negative: bool) -> Self {
+        self.allow_negative = allow_negative;
+        self
+    }
+
+    /// Only detects numbers that aren't adjacent to letters, `-`, or `_`, like the digits
+    /// of identifiers such as `order-1709152989`.
+    pub fn word_boundary(mut self, word_boundary: bool) -> Self {
+        self.word_boundary = word_boundary;
+        self
+    }
+
+    /// The bounds of each unit, in that unit.
+    pub fn bounds(&self) -> impl Iterator<Item = &(Unit, Range<i64>)> {
+        self.bounds.iter()
+    }
+
+    /// The range of the number of digits of timestamps in any bound.
+    pub fn digit_range(&self) -> RangeInclusive<usize> {
+        digit_range(self.bounds.iter().map(|(_, b)| b))
+    }
+
+    /// Returns the nanoseconds since the UNIX epoch and precision of `n` if it's a
+    /// timestamp in any of the bounds.
+    pub fn parse(&self, n: i64) -> Option<(i64, Precision)> {
+        if let Some((unit, _)) = self.bounds.iter().find(|(_, b)| b.contains(&n)) {
+            return Some((unit.to_nanos(n, self.epoch_base)?, unit.precision()));
+        }
+        // Negative numbers are detected with the bounds mirrored around the epoch, except
+        // for units with an inherent epoch
+        if n < 0 && self.allow_negative {
+            let magnitude = n.checked_neg()?;
+            if let Some((unit, _)) = self
+                .bounds
+                .iter()
+                .find(|(unit, b)| unit.is_epoch_relative() && b.contains(&magnitude))
+            {
+                return Some((unit.to_nanos(n, self.epoch_base)?, unit.precision()));
+            }
+        }
+        None
+    }
+
+    /// Returns an iterator over the units whose bounds contain `n`, in order of
+    /// precedence. More than one means the unit of `n` is ambiguous.
+    pub fn units(&self, n: i64) -> impl Iterator<Item = Unit> + '_ {
+        self.bounds
+            .iter()
+            .filter(move |(_, b)| b.contains(&n))
+            .map(|(unit, _)| *unit)
+    }
+
+    /// Returns an iterator over the integer timestamps detected in `line`.
+    pub fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        let digit_range = self.digit_range();
+        self.matches_with(line, move |candidate| {
+            let number = &line[candidate.range.clone()];
+            if candidate.is_fraction || !digit_range.contains(&number.trim_start_matches('-').len())
+            {
+                return None;
+            }
+            self.parse(number.parse().ok()?)
+        })
+    }
+
+    /// Returns an iterator over the timestamps in `line` that `parse` finds among its
+    /// numbers, for detecting them with other rules than [`Detector::matches`].
+    pub fn matches_with<'a>(
+        &'a self,
+        line: &'a str,
+        mut parse: impl FnMut(&Candidate) -> Option<(i64, Precision)> + 'a,
+    ) -> impl Iterator<Item = Match> + 'a {
+        self.candidates(line).filter_map(move |candidate| {
+            let (time_ns, precision) = parse(&candidate)?;
+            Some(Match {
+                range: candidate.range,
+                time_ns,
+                precision,
+            })
+        })
+    }
+
+    /// Returns an iterator over the numbers in `line`, skipping those within words with
+    /// `word_boundary`.
+    fn candidates<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Candidate> + 'a {
+        let bytes = line.as_bytes();
+        let mut pos = 0;
+        core::iter::from_fn(move || {
+            while pos < bytes.len() {
+                // Digits are ASCII so it's safe to scan bytes and slice `line` at these
+                // indices
+                let start = pos + bytes[pos..].iter().position(u8::is_ascii_digit)?;
+                let mut end = digit_run_end(bytes, start);
+                // A digit run followed by `.digits` is a single fractional value like a
+                // duration or a version number
+                let mut is_fraction = false;
+                while bytes.get(end) == Some(&b'.')
+                    && bytes.get(end + 1).is_some_and(u8::is_ascii_digit)
+                {
+                    is_fraction = true;
+                    end = digit_run_end(bytes, end + 1);
+                }
+                pos = end;
+                // A `-` directly before the digits is a sign, unless it's joining words or
+                // numbers
+                let start = if self.allow_negative && !is_fraction && is_sign(bytes, start) {
+                    start - 1
+                } else {
+                    start
+                };
+                let is_word_char = |b: &u8| b.is_ascii_alphabetic() || matches!(b, b'-' | b'_');
+                if self.word_boundary
+                    && (start
+                        .checked_sub(1)
+                        .is_some_and(|i| is_word_char(&bytes[i]))
+                        || bytes.get(end).is_some_and(is_word_char))
+                {
+                    continue;
+                }
+                return Some(Candidate {
+                    range: start..end,
+                    is_fraction,
+                });
+            }
+            None
+        })
+    }
+}
+
+/// Returns the range of the number of digits of integers in any of `bounds`.
+pub fn digit_range<'a>(
+    bounds: impl Iterator<Item = &'a Range<i64>> + Clone,
+) -> RangeInclusive<usize> {
+    // The number closest to zero in each bound has the fewest digits
+    let min_len = bounds
+        .clone()
+        .map(|b| digits(b.start.max(0)))
+        .min()
+        .unwrap_or(1);
+    let max_len = bounds
+        .map(|b| digits(b.start).max(digits(b.end - 1)))
+        .max()
+        .unwrap_or(1);
+    min_len..=max_len
+}
+
+/// Formats nanoseconds since the UNIX epoch as an RFC 3339 timestamp in UTC.
+pub fn format_utc(time_ns: i64, precision: Precision) -> String {
+    DateTime::from_timestamp_nanos(time_ns)
+        .format(precision.rfc_format::<false>())
+        .to_string()
+}
+
+/// Returns `true` if the digits at `start` are preceded by a `-` sign, rather than a `-`
+/// joining words or numbers.
+pub fn is_sign(bytes: &[u8], start: usize) -> bool {
+    start > 0
+        && bytes[start - 1] == b'-'
+        && (start < 2 || !bytes[start - 2].is_ascii_alphanumeric())
+}
+
+/// Converts `n` to an `i64`, saturating at its bounds.
+const fn saturate(n: i128) -> i64 {
+    if n > i64::MAX as i128 {
+        i64::MAX
+    } else if n < i64::MIN as i128 {
+        i64::MIN
+    } else {
+        n as i64
+    }
+}
+
+/// Returns the number of decimal digits in `n`, ignoring any sign.
+pub fn digits(n: i64) -> usize {
+    n.unsigned_abs()
+        .checked_ilog10()
+        .map_or(1, |d| d as usize + 1)
+}
+
+/// Returns the index of the first non-digit byte at or after `start`.
+pub fn digit_run_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| !b.is_ascii_digit())
+        .map_or(bytes.len(), |i| start + i)
+}
@@ -1,163 +1,2877 @@
+mod access_log;
+mod arithmetic;
+mod binary;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+#[cfg(feature = "cloud")]
+mod cloud;
+#[cfg(feature = "parquet")]
+mod columnar;
+mod compact;
+#[cfg(unix)]
+mod docker;
+mod duration;
+mod encoded;
+mod encoding;
+mod explain;
+mod filter;
+mod fiscal;
+mod follow;
+mod hist;
+mod holiday;
+#[cfg(feature = "http")]
+mod http;
+mod ids;
+mod iso;
+mod journal;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod leap;
+mod output;
+#[cfg(feature = "plugin")]
+mod plugin;
+mod preset;
+#[cfg(feature = "progress")]
+mod progress;
+#[cfg(feature = "protobuf")]
+mod proto;
+mod relative;
+mod repl;
+mod rewrite;
+mod scrub;
+mod serve;
+mod session;
+mod style;
+mod syslog;
+mod template;
+mod timing;
+#[cfg(feature = "self-update")]
+mod update;
+#[cfg(feature = "tui")]
+mod view;
+
 use std::{
+    cell::{Cell, RefCell},
     fs::File,
-    io::{stdin, stdout, BufRead, BufReader, BufWriter, Write},
-    ops::{Range, RangeInclusive},
-    path::PathBuf,
+    io::{stdin, stdout, BufRead, BufReader, IsTerminal, Read, Write},
+    net::SocketAddr,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Instant,
 };
 
-use chrono::{DateTime, Local, SecondsFormat, TimeZone};
-use clap::Parser;
+use anyhow::{bail, Context};
+use chrono::{
+    DateTime, Local, LocalResult, Locale, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
+use clap::{CommandFactory, Parser, ValueEnum};
+use encoded::Base64Mode;
+use epoch_to::{Candidate, Detector, Match, Precision, Unit};
+use fiscal::{Calendar, Fiscal};
+use ids::IdMode;
+use relative::TimeArg;
+use style::Style;
+use template::Placeholder;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Clone, Debug)]
 #[clap(version, about)]
+#[clap(group(clap::ArgGroup::new("time_range").args(["since", "until"]).multiple(true)))]
 struct Args {
-    /// Input file to read from; omit reading from STDIN or arguments
+    /// Input file to read from; omit reading from STDIN or arguments. With the `http`
+    /// feature, this can also be an HTTP or HTTPS URL, and with the `cloud` feature an
+    /// `s3://BUCKET/KEY` or `gs://BUCKET/KEY` object URL, which is decompressed if it's
+    /// gzipped. On Unix, `unix:PATH` reads from the Unix domain socket at `PATH`
     #[clap(short, long, value_name = "FILE")]
     input: Option<PathBuf>,
-    /// Output file to write to; omit writing to STDOUT
+    /// Keep reading `--input` as it grows, like `tail -F`, starting over when it's
+    /// truncated or replaced by log rotation
+    #[clap(short, long, requires = "input", conflicts_with = "no_line_mode")]
+    follow: bool,
+    /// `NAME: VALUE` header to send when requesting an `--input` URL, e.g. for
+    /// authorization. Can be repeated
+    #[cfg(feature = "http")]
+    #[clap(long, value_name = "HEADER", requires = "input")]
+    header: Vec<String>,
+    /// Format of the input. Binary formats are converted to JSON lines
+    #[clap(long, value_name = "FORMAT", value_enum, default_value_t = InputFormat::Text, conflicts_with = "strings")]
+    input_format: InputFormat,
+    /// Character encoding of text input, which is transcoded to UTF-8
+    #[clap(long, value_name = "ENCODING", value_enum, default_value_t = encoding::Encoding::Utf8, conflicts_with = "strings")]
+    encoding: encoding::Encoding,
+    /// Comma-separated glob patterns of the keys whose values to convert in structured
+    /// input or with `--kv`; omit to convert any value detected as a timestamp
+    #[clap(long, value_name = "KEYS", value_delimiter = ',')]
+    keys: Vec<String>,
+    /// Only convert the values of `--keys` in `key=value` pairs or JSON `"key": value`
+    /// members of text input, like logfmt
+    #[clap(long, requires = "keys")]
+    kv: bool,
+    /// Comma-separated numbers of the fields to convert in comma-separated lines,
+    /// starting from 1. Each can be in its own unit, like `3:ns,7:s`, instead of detecting
+    /// it from the magnitude
+    #[clap(long, value_name = "COLUMNS", value_parser = parse_column, value_delimiter = ',')]
+    columns: Vec<(usize, Option<Unit>)>,
+    /// Use the options for a known log format: `cme-gateway`, `databento`, `kubernetes`, `nginx`,
+    /// `strace`, or a preset defined in the config file. Other options take precedence
+    #[clap(long, value_name = "PRESET")]
+    preset: Vec<String>,
+    /// Convert the nanosecond `ts_event`, `ts_recv`, `ts_ref`, `expiration`, and
+    /// `activation` fields of Databento's JSON encoding of DBN records, and nothing else,
+    /// like prices and order IDs. Shorthand for `--preset databento`
+    #[clap(long)]
+    databento: bool,
+    /// Read the input from the system clipboard
+    #[cfg(feature = "clipboard")]
+    #[clap(long, conflicts_with_all = ["input", "input_format", "strings"])]
+    clipboard: bool,
+    /// Output file to write to; omit writing to STDOUT. On Unix, `unix:PATH` writes to the
+    /// Unix domain socket at `PATH`
     #[clap(short, long, value_name = "FILE")]
     output: Option<PathBuf>,
-    /// Threshold for detecting dates, in +/- years
+    /// Also write the output to STDOUT, to watch a conversion to `--output`
+    #[clap(long, requires = "output")]
+    tee: bool,
+    /// Append to `--output` instead of truncating it, like `>>`
+    #[clap(long, requires = "output")]
+    append: bool,
+    /// Write `--output` in place instead of to a temporary file that replaces it once the
+    /// conversion succeeds. Files other than regular ones, like FIFOs, are always written
+    /// in place, as are appended ones and ones written while following the input
+    #[clap(long, requires = "output")]
+    no_atomic: bool,
+    /// Show the progress of converting `--input` on STDERR. The default when only STDERR
+    /// is a terminal
+    #[cfg(feature = "progress")]
+    #[clap(long, requires = "input", conflicts_with_all = ["no_progress", "follow"])]
+    progress: bool,
+    /// Don't show the progress of converting `--input`
+    #[cfg(feature = "progress")]
+    #[clap(long)]
+    no_progress: bool,
+    /// Also copy the output to the system clipboard
+    #[cfg(feature = "clipboard")]
+    #[clap(long)]
+    copy: bool,
+    /// Threshold for detecting dates, in +/- years; 0 disables it, together with `--unit`
     #[clap(short, long, value_name = "YEARS", default_value_t = 8)]
     threshold: i32,
+    /// Detect any number regardless of its date, together with `--unit`. Equivalent to
+    /// `--threshold 0`
+    #[clap(
+        long,
+        requires = "unit",
+        conflicts_with_all = ["threshold", "past_years", "future_years", "min_date", "max_date"],
+    )]
+    no_threshold: bool,
+    /// Threshold for detecting dates in the past, in years, instead of `--threshold`
+    #[clap(long, value_name = "YEARS", conflicts_with = "min_date")]
+    past_years: Option<u32>,
+    /// Threshold for detecting dates in the future, in years, instead of `--threshold`
+    #[clap(long, value_name = "YEARS", conflicts_with = "max_date")]
+    future_years: Option<u32>,
+    /// Only detect timestamps at or after this time, given like `--since`, instead of
+    /// `--threshold` years ago
+    #[clap(long, value_name = "DATE", value_parser = relative::parse)]
+    min_date: Option<TimeArg>,
+    /// Only detect timestamps before this time, given like `--since`, instead of
+    /// `--threshold` years from now
+    #[clap(long, value_name = "DATE", value_parser = relative::parse)]
+    max_date: Option<TimeArg>,
+    /// Fail on numbers within the bounds of more than one unit, e.g. with a wide
+    /// threshold, instead of picking the first. Only warn with `--verbose`
+    #[clap(long)]
+    strict: bool,
+    /// Only detect timestamps in this unit instead of inferring it from the magnitude
+    #[clap(short, long, value_name = "UNIT", value_enum)]
+    unit: Option<Unit>,
+    /// Detect numbers whose magnitude fits more than one unit, as with a wide threshold,
+    /// in this unit instead of the coarsest
+    #[clap(long, value_name = "UNIT", value_enum, conflicts_with = "unit")]
+    prefer_unit: Option<Unit>,
     /// Localize timestamps
     #[clap(short, long)]
     local: bool,
+    /// How to render timestamps
+    #[clap(long, value_name = "STYLE", value_enum, default_value_t = Style::Rfc3339)]
+    style: Style,
+    /// Render timestamps with this `strftime` format instead of a `--style`, e.g.
+    /// `'%Y-%m-%d %H:%M:%S%.3f'`
+    #[clap(long, value_name = "FORMAT", value_parser = style::parse_format, conflicts_with = "style")]
+    format: Option<String>,
+    /// The sub-second precision to render timestamps with instead of that of the detected
+    /// unit. Dates keep theirs
+    #[clap(long, value_name = "PRECISION", value_enum, default_value_t = OutputPrecision::Auto)]
+    precision: OutputPrecision,
+    /// Floor timestamps to this interval before rendering them, e.g. `1m` to bucket lines
+    /// by minute. Intervals of whole days start at midnight UTC
+    #[clap(
+        long,
+        value_name = "INTERVAL",
+        value_parser = parse_interval,
+        conflicts_with = "verify_passthrough"
+    )]
+    truncate_to: Option<i64>,
+    /// Render month and day names in `--format` and `--style unix-date` in this locale,
+    /// e.g. `de_DE`
+    #[clap(long, value_name = "LOCALE", value_parser = style::parse_locale)]
+    locale: Option<Locale>,
+    /// Render timestamps in the given IANA time zone, e.g. `America/Chicago`. Can be
+    /// repeated to also render them in the other zones, separated by `--tz-separator`
+    #[clap(long, value_name = "ZONE", value_parser = parse_zone, conflicts_with = "local")]
+    tz: Vec<Zone>,
+    /// The text between the renderings of a timestamp in each `--tz` zone
+    #[clap(long, value_name = "SEPARATOR", default_value = " | ")]
+    tz_separator: String,
+    /// Comma-separated `PATTERN=ZONE` overrides of the time zone for input files whose
+    /// name matches the glob `PATTERN`; the first match wins
+    #[clap(
+        long,
+        value_name = "PATTERN=ZONE",
+        value_parser = parse_tz_mapping,
+        value_delimiter = ',',
+        requires = "input"
+    )]
+    tz_map: Vec<(String, Zone)>,
+    /// Render timestamps in UTC followed by their time of day in the local zone or the
+    /// `--tz` zone, e.g. `2024-02-28T20:43:09Z (14:43:09 CST)`
+    #[clap(long)]
+    both: bool,
+    /// How to handle wall-clock times that are ambiguous because they fall in a daylight
+    /// saving time overlap, both in localized output and in syslog and compact dates read
+    /// in `--tz`. Wall-clock times skipped when clocks are set forward are always
+    /// converted with the offset before the gap and flagged with `(nonexistent)`
+    #[clap(long, value_name = "POLICY", value_enum, default_value_t = DstPolicy::Ignore)]
+    dst_policy: DstPolicy,
+    /// Treat a `-` directly before a number as its sign, detecting negative numbers whose
+    /// magnitude is within the bounds
+    #[clap(long)]
+    allow_negative: bool,
+    /// Zero point of seconds, milliseconds, and nanoseconds: `unix`, `cocoa`
+    /// (2001-01-01), `gps` (1980-01-06), or an RFC 3339 date or date-time
+    #[clap(long, value_name = "BASE", value_parser = parse_epoch_base, default_value = "unix")]
+    epoch_base: i64,
+    /// The time scale of detected timestamps. TAI and GPS timestamps count leap seconds,
+    /// which are subtracted to convert them to UTC
+    #[clap(long, value_name = "SCALE", value_enum, default_value_t = leap::Scale::Utc)]
+    scale: leap::Scale,
+    /// A leap-second table for `--scale` in the IETF `leap-seconds.list` format instead
+    /// of the bundled one, which ends with the leap second of 2016-12-31
+    #[clap(long, value_name = "FILE")]
+    leap_seconds: Option<PathBuf>,
+    /// Also detect Windows `FILETIME` values: 100-nanosecond intervals since 1601-01-01
+    #[clap(long)]
+    filetime: bool,
+    /// Also detect .NET `DateTime.Ticks` values: 100-nanosecond intervals since 0001-01-01
+    #[clap(long)]
+    dotnet_ticks: bool,
+    /// Also detect days, hours, and minutes since the epoch, like Arrow `DATE32` values
+    /// and hourly bucket IDs. Days are rendered as dates
+    #[clap(long, conflicts_with = "unit")]
+    coarse_units: bool,
+    /// Comma-separated fiscal context to append to each timestamp
+    #[clap(long, value_name = "CONTEXT", value_enum, value_delimiter = ',')]
+    fiscal: Vec<Fiscal>,
+    /// The first month of the fiscal year for `--fiscal quarter`
+    #[clap(
+        long,
+        value_name = "MONTH",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=12)
+    )]
+    fiscal_year_start: u32,
+    /// The listed contract months for `--fiscal contract` as futures month codes
+    #[clap(long, value_name = "CODES", default_value = "HMUZ", value_parser = fiscal::parse_cycle)]
+    contract_cycle: String,
+    /// Comma-separated venues whose regular trading hours to annotate each timestamp with,
+    /// marking whether the venue is open and the date of the session
+    #[clap(long, value_name = "VENUE", value_enum, value_delimiter = ',')]
+    session: Vec<session::Venue>,
+    /// Flag timestamps on weekends or `--holidays` in the zone they're rendered in, e.g.
+    /// to spot misparsed market data
+    #[clap(long)]
+    annotate_calendar: bool,
+    /// A file of holidays for `--annotate-calendar` with a `YYYY-MM-DD` date and an
+    /// optional name per line, e.g. `2024-02-19 Presidents Day`
+    #[clap(long, value_name = "FILE", requires = "annotate_calendar")]
+    holidays: Option<PathBuf>,
+    /// Also detect Excel serial dates with a fractional time of day: days since 1899-12-30
+    #[clap(long)]
+    excel: bool,
+    /// Also detect Julian days or modified Julian days, with or without a fractional time of
+    /// day
+    #[clap(long, value_name = "KIND", value_enum)]
+    julian: Option<Julian>,
+    /// Replace matches of this regex with the `--with` template
+    #[clap(long, value_name = "REGEX", requires = "with")]
+    rewrite: Option<String>,
+    /// Template for `--rewrite`, where `${NAME}` inserts a capture group, `${NAME:ts}`
+    /// inserts it with any timestamps converted, and `$$` inserts a `$`
+    #[clap(long, value_name = "TEMPLATE", requires = "rewrite")]
+    with: Option<String>,
+    /// Detect and convert timestamps with the WebAssembly module at FILE, which can be
+    /// given more than once
+    #[cfg(feature = "plugin")]
+    #[clap(long, value_name = "FILE")]
+    plugin: Vec<PathBuf>,
+    /// Convert timestamps inside base64-encoded text payloads, either re-encoding the
+    /// converted payload or annotating the original with it
+    #[clap(long, value_name = "MODE", value_enum)]
+    decode_base64: Option<Base64Mode>,
+    /// Detect version 1 and 7 UUIDs and append their creation time, or replace them with
+    /// it with `--uuid=replace`
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "annotate"
+    )]
+    uuid: Option<IdMode>,
+    /// Detect 64-bit Snowflake IDs created within the threshold and append their creation
+    /// time, or replace them with it with `--snowflake=replace`
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "annotate"
+    )]
+    snowflake: Option<IdMode>,
+    /// The epoch of `--snowflake` IDs: `twitter`, `discord`, or milliseconds since the
+    /// UNIX epoch
+    #[clap(
+        long,
+        value_name = "EPOCH",
+        value_parser = ids::parse_snowflake_epoch,
+        default_value = "twitter",
+        requires = "snowflake"
+    )]
+    snowflake_epoch: i64,
+    /// Detect MongoDB ObjectIds created within the threshold and append their creation
+    /// time, or replace them with it with `--objectid=replace`
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "annotate"
+    )]
+    objectid: Option<IdMode>,
+    /// Detect ULIDs created within the threshold and append their creation time, or
+    /// replace them with it with `--ulid=replace`
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "annotate"
+    )]
+    ulid: Option<IdMode>,
+    /// Detect KSUIDs created within the threshold and append their creation time, or
+    /// replace them with it with `--ksuid=replace`
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "annotate"
+    )]
+    ksuid: Option<IdMode>,
+    /// Annotate JSON Web Tokens with their `iat`, `nbf`, and `exp` claims, without
+    /// verifying their signatures
+    #[clap(long)]
+    jwt: bool,
+    /// Reformat durations with a unit suffix like `547261us` in the largest unit that
+    /// keeps them at least 1, like `547.261ms`
+    #[clap(long)]
+    durations: bool,
+    /// Convert RFC 3164 syslog dates like `Feb 28 20:43:09`, in the `--tz` or `--local`
+    /// zone, inferring the most recent year within the threshold
+    #[clap(long)]
+    syslog: bool,
+    /// Re-render existing RFC 3339 timestamps like `2024-02-28T21:43:09.456+01:00` in the
+    /// output zone, keeping their precision
+    #[clap(long)]
+    reformat_iso: bool,
+    /// Convert common log format dates like `28/Feb/2024:20:43:09 +0000` from Apache and
+    /// nginx access logs
+    #[clap(long)]
+    access_log: bool,
+    /// Convert compact dates like `20240228` and date-times like `20240228204309` within
+    /// the threshold, in the `--tz` or `--local` zone
+    #[clap(long)]
+    compact_dates: bool,
+    /// Decode base64- or hex-encoded protobuf payloads with the given serialized
+    /// `FileDescriptorSet` and print them as JSON with any timestamps converted
+    #[cfg(feature = "protobuf")]
+    #[clap(long, value_name = "FILE", requires = "proto_type")]
+    proto_descriptor: Option<PathBuf>,
+    /// The fully-qualified name of the protobuf message type of payloads
+    #[cfg(feature = "protobuf")]
+    #[clap(long, value_name = "NAME", requires = "proto_descriptor")]
+    proto_type: Option<String>,
     /// Quote formatted timestamps with `"..."`
-    #[clap(short, long)]
+    #[clap(short, long, conflicts_with_all = ["wrap", "quote_char"])]
     quote: bool,
+    /// Wrap formatted timestamps between the two given characters, e.g. `[]` or `<>`
+    #[clap(long, value_name = "OPEN><CLOSE", value_parser = parse_wrap, conflicts_with = "quote_char")]
+    wrap: Option<(char, char)>,
+    /// Quote formatted timestamps with this character on both sides, e.g. `'`
+    #[clap(long, value_name = "CHAR")]
+    quote_char: Option<char>,
+    /// Replace each timestamp with this template, where `{iso}` inserts it formatted,
+    /// `{original}` inserts it as written, `{unit}` its unit, `{epoch_s}` and `{epoch_ns}`
+    /// its seconds or nanoseconds since the UNIX epoch, `{relative}` how long ago or from
+    /// now it is, and `{{` and `}}` insert braces
+    #[clap(long, value_name = "TEMPLATE", conflicts_with_all = ["quote", "wrap", "quote_char"])]
+    template: Option<String>,
+    /// Only consider numbers with at least this many digits; defaults to the fewest digits
+    /// of any detectable timestamp
+    #[clap(long, value_name = "N")]
+    min_digits: Option<usize>,
+    /// Only consider numbers with at most this many digits; defaults to the most digits of
+    /// any detectable timestamp
+    #[clap(long, value_name = "N")]
+    max_digits: Option<usize>,
+    /// Skip numbers directly next to a letter, `-`, or `_`, like those in identifiers
+    /// such as `order_1709152989456_ab`
+    #[clap(long)]
+    word_boundary: bool,
+    /// Only detect seconds with a fractional part at the start of a line, like the times
+    /// of `strace -ttt`, and not whole numbers
+    #[clap(long)]
+    fractions_only: bool,
+    /// Only convert the first N timestamps of each line
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["occurrence", "input_format"]
+    )]
+    max_per_line: Option<usize>,
+    /// Only convert the Nth timestamp of each line, counting from 1
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with = "input_format"
+    )]
+    occurrence: Option<usize>,
+    /// Pad formatted timestamps to the width of the original numbers to preserve column
+    /// alignment, dropping fractional digits or the time of day of wider ones and failing
+    /// if even the date is wider
+    #[clap(long)]
+    fixed_width: bool,
+    /// Pad formatted timestamps with spaces to a constant width, truncating fractional
+    /// digits of wider ones; defaults to the width with the precision of the most precise
+    /// detectable unit
+    #[clap(
+        long,
+        value_name = "WIDTH",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with = "fixed_width"
+    )]
+    pad: Option<Option<usize>>,
+    /// Replace timestamps with this placeholder instead of formatting them. `{n}` in the
+    /// placeholder is replaced with a sequence number that's the same for repeated
+    /// timestamps
+    #[clap(long, value_name = "PLACEHOLDER")]
+    scrub: Option<String>,
+    /// Record a JSON object from each `--scrub` placeholder to its original timestamp in
+    /// this file
+    #[clap(long, value_name = "FILE", requires = "scrub")]
+    map_file: Option<PathBuf>,
+    /// Restore the original timestamps of placeholders recorded with `--map-file`
+    /// instead of converting timestamps
+    #[clap(long, value_name = "MAP_FILE", conflicts_with_all = ["scrub", "extract"])]
+    unscrub: Option<PathBuf>,
+    /// Check that each converted line only differs from the input in the detected
+    /// timestamps, and that they convert back to the original times, failing otherwise
+    #[clap(long, conflicts_with_all = ["extract", "unscrub"])]
+    verify_passthrough: bool,
+    /// Rewrite timestamps as epochs in this unit instead of formatting them
+    #[clap(long, value_name = "UNIT", value_enum, conflicts_with = "scrub")]
+    to_unit: Option<Unit>,
+    /// Flush the output after each line. The default when writing to a terminal
+    #[clap(long, conflicts_with = "block_buffered")]
+    line_buffered: bool,
+    /// Only flush the output when its buffer fills, even when writing to a terminal.
+    /// The default otherwise
+    #[clap(long)]
+    block_buffered: bool,
+    /// Leave the text unchanged but mark the timestamps that would be converted, in color
+    /// when writing to a terminal or otherwise between `>>>` and `<<<`
+    #[clap(long, conflicts_with_all = ["extract", "verify_passthrough"])]
+    dry_run: bool,
+    /// Log each conversion to STDERR with its line number, byte range, and unit
+    #[clap(short, long)]
+    verbose: bool,
+    /// Exit with status 1 if no timestamps were converted, like `grep`
+    #[clap(long)]
+    fail_if_none: bool,
+    /// Report the bytes and lines read, the timestamps converted, and the throughput to
+    /// STDERR on exit
+    #[clap(long)]
+    timing: bool,
+    /// Process the input in fixed-size chunks instead of lines, so huge lines don't have
+    /// to fit in memory. Chunks are split between tokens
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "extract", "tsv", "verify_passthrough", "unscrub", "max_per_line", "occurrence",
+        ]
+    )]
+    no_line_mode: bool,
+    /// Stream lines longer than this many bytes in pieces split between tokens instead of
+    /// reading them into memory whole, still converting the timestamps in each piece
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["tsv", "verify_passthrough", "no_line_mode"]
+    )]
+    max_line_bytes: Option<u64>,
+    /// Separate input and output records with NUL instead of newlines, like
+    /// `find -print0`, so records can contain newlines
+    #[clap(short = '0', long, conflicts_with = "no_line_mode")]
+    null: bool,
+    /// Prefix each output line with its `LINE:` number in the input
+    #[clap(
+        short = 'n',
+        long,
+        conflicts_with_all = ["extract", "tsv", "output_format", "no_line_mode"]
+    )]
+    line_numbers: bool,
+    /// Only print lines with at least one converted timestamp
+    #[clap(long, conflicts_with_all = ["invert", "no_line_mode"])]
+    only_matches: bool,
+    /// Only print lines without any converted timestamps, e.g. to find ones that weren't
+    /// detected
+    #[clap(long, conflicts_with = "no_line_mode")]
+    invert: bool,
+    /// Write a `-- gap of DURATION --` line before a line whose timestamp is more than
+    /// this duration, like `5s` or `1m`, after the previous timestamp, e.g. to spot
+    /// outages
+    #[clap(
+        long,
+        value_name = "DURATION",
+        value_parser = duration::parse,
+        conflicts_with = "no_line_mode"
+    )]
+    gaps: Option<i64>,
+    /// Report `--gaps` on STDERR instead, as JSON lines output always does
+    #[clap(long, requires = "gaps")]
+    gaps_to_stderr: bool,
+    /// Only keep lines whose first timestamp is at or after this time: an RFC 3339 date
+    /// or date-time, a duration ago like `2h`, or midnight of a day like `today`,
+    /// `yesterday`, or `monday` in the zone timestamps are rendered in
+    #[clap(
+        long,
+        value_name = "TIME",
+        value_parser = relative::parse,
+        conflicts_with_all = ["input_format", "no_line_mode", "max_line_bytes"]
+    )]
+    since: Option<TimeArg>,
+    /// Only keep lines whose first timestamp is before this time, given like `--since`
+    #[clap(
+        long,
+        value_name = "TIME",
+        value_parser = relative::parse,
+        conflicts_with_all = ["input_format", "no_line_mode", "max_line_bytes"]
+    )]
+    until: Option<TimeArg>,
+    /// Also keep this many lines after each line in the `--since`/`--until` range, like
+    /// lines without timestamps continuing it
+    #[clap(short = 'A', long, value_name = "NUM", requires = "time_range")]
+    after_context: Option<usize>,
+    /// Also keep this many lines before each line in the `--since`/`--until` range
+    #[clap(short = 'B', long, value_name = "NUM", requires = "time_range")]
+    before_context: Option<usize>,
+    /// Also keep this many lines before and after each line in the `--since`/`--until`
+    /// range
+    #[clap(short = 'C', long, value_name = "NUM", requires = "time_range")]
+    context: Option<usize>,
+    /// Only print the converted timestamps, one per line
+    #[clap(short = 'x', long)]
+    extract: bool,
+    /// Format of the output. Text input is rewritten by default, or described by a JSON
+    /// object per timestamp with `jsonl`
+    #[clap(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        conflicts_with_all = [
+            "input_format", "extract", "tsv", "unscrub", "verify_passthrough", "dry_run",
+            "no_line_mode",
+        ]
+    )]
+    output_format: OutputFormat,
+    /// Prefix extracted timestamps with their `LINE:BYTE:` offset in the input
+    #[clap(long, requires = "extract")]
+    with_offset: bool,
+    /// Print the first timestamp of each line, its conversion, and the rest of the line
+    /// as tab-separated values, for joining the output with the input
+    #[clap(long, conflicts_with_all = ["extract", "unscrub", "verify_passthrough", "dry_run"])]
+    tsv: bool,
+    /// Prompt for values and show all their interpretations, the default when STDIN is a
+    /// terminal and there's no other input
+    #[clap(long, conflicts_with_all = ["input", "input_format", "strings"])]
+    interactive: bool,
     /// If provided, convert command line arguments instead of STDIN or a file
     #[clap(value_name = "TEXT")]
     strings: Vec<String>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+enum Command {
+    /// Explain what time a number represents: the unit it's detected in, its rendering
+    /// in different zones, its day of the week and ISO week, how long ago it is, and its
+    /// value in other units
+    Explain {
+        /// The number to explain
+        #[clap(value_name = "NUMBER", allow_hyphen_values = true)]
+        number: String,
+    },
+    /// Add a duration like `1h30m`, `250ms`, or `3d` to an epoch or RFC 3339 date-time,
+    /// printing the result both as an epoch and formatted
+    Add {
+        /// The epoch or date-time to add to
+        #[clap(value_name = "TIMESTAMP", allow_hyphen_values = true)]
+        timestamp: String,
+        /// The duration to add
+        #[clap(value_name = "DURATION", value_parser = duration::parse)]
+        duration: i64,
+    },
+    /// Subtract a duration like `1h30m`, `250ms`, or `3d` from an epoch or RFC 3339
+    /// date-time, printing the result both as an epoch and formatted
+    Sub {
+        /// The epoch or date-time to subtract from
+        #[clap(value_name = "TIMESTAMP", allow_hyphen_values = true)]
+        timestamp: String,
+        /// The duration to subtract
+        #[clap(value_name = "DURATION", value_parser = duration::parse)]
+        duration: i64,
+    },
+    /// Write the logs of a Docker container with their timestamps converted, reading them
+    /// from the Engine API's socket: `DOCKER_HOST` if it's a `unix://` URL, like Podman's,
+    /// or `/var/run/docker.sock`
+    #[cfg(unix)]
+    Docker {
+        /// The name or ID of the container
+        #[clap(value_name = "CONTAINER")]
+        container: String,
+        /// Keep writing new lines as they're logged
+        #[clap(short, long)]
+        follow: bool,
+    },
+    /// Count the timestamps detected in the input or `--input` per bucket of time, from the
+    /// first to the last, and chart them
+    Hist {
+        /// The duration of each bucket, like `1m`, `250ms`, or `1d`
+        #[clap(long, value_name = "DURATION", value_parser = duration::parse, default_value = "1m")]
+        bucket: i64,
+        /// How to print the counts
+        #[clap(long, value_name = "FORMAT", value_enum, default_value_t = hist::HistFormat::Chart)]
+        output_format: hist::HistFormat,
+        /// The width of the longest bar
+        #[clap(long, value_name = "COLUMNS", default_value_t = 60)]
+        width: usize,
+    },
+    /// Write the systemd journal's entries as JSON lines with their times and the
+    /// timestamps in their messages converted, by running `journalctl`
+    Journal {
+        /// Only write the entries of this systemd unit. Can be given more than once
+        #[clap(short, long, value_name = "UNIT")]
+        unit: Vec<String>,
+        /// Keep writing new entries as they're added
+        #[clap(short, long)]
+        follow: bool,
+    },
+    /// Consume a Kafka topic, writing each message's payload with its timestamps
+    /// converted. `--keys` selects the fields of JSON or `key=value` payloads to convert
+    #[cfg(feature = "kafka")]
+    Kafka {
+        /// Comma-separated `HOST:PORT` addresses of brokers to bootstrap from
+        #[clap(long, value_name = "BROKERS", value_delimiter = ',', required = true)]
+        brokers: Vec<String>,
+        /// The topic to consume from every partition of
+        #[clap(long, value_name = "TOPIC")]
+        topic: String,
+        /// Start from the oldest message kept instead of new ones
+        #[clap(long)]
+        from_beginning: bool,
+        /// Prefix each message with the time the broker assigned it
+        #[clap(long)]
+        message_time: bool,
+    },
+    /// Write a roff man page generated from these options to STDOUT, or man pages for
+    /// `epoch` and each subcommand to a directory
+    Man {
+        /// The directory to write `epoch.1` and the subcommands' pages to
+        #[clap(value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+    /// Print the rows of a Parquet file as tab-separated values with its timestamp and
+    /// date columns formatted
+    #[cfg(feature = "parquet")]
+    Parquet {
+        /// The Parquet file to print
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+        /// Comma-separated names of the columns to print; omit to print every column
+        #[clap(long, value_name = "COLUMNS", value_delimiter = ',')]
+        columns: Vec<String>,
+    },
+    /// Listen for newline-separated messages over TCP and a message per datagram over
+    /// UDP, like syslog, writing them to the output with their timestamps converted
+    Serve {
+        /// The address to listen on for both TCP and UDP
+        #[clap(long, value_name = "ADDR", default_value = "127.0.0.1:5514")]
+        listen: SocketAddr,
+    },
+    /// Replace this binary with the latest release if it's newer, after verifying its
+    /// checksum
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Only check whether a newer release is available
+        #[clap(long)]
+        check: bool,
+    },
+    /// Browse a file in a pager with its timestamps converted and highlighted. Keys
+    /// toggle the zone between local time and UTC, cycle the precision, and jump to a
+    /// given time
+    #[cfg(feature = "tui")]
+    View {
+        /// The file to view
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    /// Lines of text
+    Text,
+    /// Concatenated MessagePack values
+    Msgpack,
+    /// Concatenated CBOR values
+    Cbor,
+    /// Databento Binary Encoding records, optionally zstd-compressed
+    #[cfg(feature = "dbn")]
+    Dbn,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// The input with its timestamps converted
+    Text,
+    /// A JSON object per timestamp with its line number, byte range, original text,
+    /// unit, nanoseconds since the UNIX epoch, and RFC 3339 rendering in UTC
+    Jsonl,
+}
+
+/// The time zone timestamps are rendered in.
+#[derive(Clone, Copy, Debug)]
+enum Zone {
+    Utc,
+    Local,
+    Tz(Tz),
+}
+
+impl Zone {
+    /// Returns the wall-clock time of `time` in this zone.
+    fn wall_time(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Utc => time.naive_utc(),
+            Zone::Local => time.with_timezone(&Local).naive_local(),
+            Zone::Tz(tz) => time.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// Converts a wall-clock time in this zone to UTC, taking the earlier time if it's
+    /// ambiguous. Returns `None` if it's skipped, e.g. when clocks are set forward.
+    fn to_utc(self, wall_time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(wall_time.and_utc()),
+            Zone::Local => Local
+                .from_local_datetime(&wall_time)
+                .earliest()
+                .map(|time| time.to_utc()),
+            Zone::Tz(tz) => tz
+                .from_local_datetime(&wall_time)
+                .earliest()
+                .map(|time| time.to_utc()),
+        }
+    }
+
+    /// Converts a wall-clock time in this zone to UTC, choosing between the times of an
+    /// ambiguous one per `policy`, and whether it's nonexistent because clocks were set
+    /// forward past it, in which case it's converted with the offset before the gap.
+    fn resolve(self, wall_time: NaiveDateTime, policy: DstPolicy) -> Option<(DateTime<Utc>, bool)> {
+        fn resolve<Z: TimeZone>(
+            zone: &Z,
+            wall_time: NaiveDateTime,
+            policy: DstPolicy,
+        ) -> Option<(DateTime<Utc>, bool)> {
+            match zone.from_local_datetime(&wall_time) {
+                LocalResult::Single(time) => Some((time.to_utc(), false)),
+                LocalResult::Ambiguous(_, latest) if policy == DstPolicy::Latest => {
+                    Some((latest.to_utc(), false))
+                }
+                LocalResult::Ambiguous(earliest, _) => Some((earliest.to_utc(), false)),
+                LocalResult::None => {
+                    let before = zone
+                        .from_local_datetime(&(wall_time - chrono::Duration::days(1)))
+                        .earliest()?;
+                    let offset = before.offset().fix().local_minus_utc();
+                    let time = wall_time - chrono::Duration::seconds(offset.into());
+                    Some((time.and_utc(), true))
+                }
+            }
+        }
+        match self {
+            Zone::Utc => Some((wall_time.and_utc(), false)),
+            Zone::Local => resolve(&Local, wall_time, policy),
+            Zone::Tz(tz) => resolve(&tz, wall_time, policy),
+        }
+    }
+
+    /// Returns `true` if the wall-clock time of `time` in this zone occurs more than once,
+    /// e.g. when clocks are set back at the end of daylight saving time.
+    fn is_ambiguous(self, time: DateTime<Utc>) -> bool {
+        fn check<Z: TimeZone>(zone: &Z, time: DateTime<Utc>) -> bool {
+            let wall_time = time.with_timezone(zone).naive_local();
+            matches!(
+                zone.from_local_datetime(&wall_time),
+                LocalResult::Ambiguous(..)
+            )
+        }
+        match self {
+            Zone::Utc => false,
+            Zone::Local => check(&Local, time),
+            Zone::Tz(tz) => check(&tz, time),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DstPolicy {
+    /// Render ambiguous times like any other, and read ambiguous wall-clock times as
+    /// the earlier time
+    Ignore,
+    /// Append `(ambiguous)` to ambiguous times, and read ambiguous wall-clock times as
+    /// the earlier time
+    Annotate,
+    /// Read ambiguous wall-clock times as the earlier time, before clocks are set back
+    Earliest,
+    /// Read ambiguous wall-clock times as the later time, after clocks are set back
+    Latest,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputPrecision {
+    /// The precision of the detected unit
+    Auto,
+    /// Seconds
+    #[value(name = "s")]
+    Seconds,
+    /// Milliseconds
+    #[value(name = "ms")]
+    Millis,
+    /// Microseconds
+    #[value(name = "us")]
+    Micros,
+    /// Nanoseconds
+    #[value(name = "ns")]
+    Nanos,
+}
+
+impl OutputPrecision {
+    const fn precision(self) -> Option<Precision> {
+        match self {
+            OutputPrecision::Auto => None,
+            OutputPrecision::Seconds => Some(Precision::Seconds),
+            OutputPrecision::Millis => Some(Precision::Millis),
+            OutputPrecision::Micros => Some(Precision::Micros),
+            OutputPrecision::Nanos => Some(Precision::Nanos),
+        }
+    }
+}
+
+fn parse_zone(s: &str) -> Result<Zone, String> {
+    match s {
+        "UTC" | "Z" => Ok(Zone::Utc),
+        "local" => Ok(Zone::Local),
+        _ => s.parse().map(Zone::Tz).map_err(|e| format!("{e}")),
+    }
+}
+
+/// Parses an epoch base to seconds since the UNIX epoch.
+fn parse_epoch_base(s: &str) -> Result<i64, String> {
+    match s {
+        "unix" => Ok(0),
+        "cocoa" => Ok(978_307_200),
+        "gps" => Ok(315_964_800),
+        _ => parse_date(s).map_err(|_| {
+            format!("expected `unix`, `cocoa`, `gps`, or an RFC 3339 date, found `{s}`")
+        }),
+    }
+}
+
+/// Parses an RFC 3339 date or date-time to seconds since the UNIX epoch. Dates are
+/// midnight UTC.
+fn parse_date(s: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp())
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc().timestamp())
+        })
+        .map_err(|_| format!("expected an RFC 3339 date or date-time, found `{s}`"))
+}
+
+/// Parses a field number starting from 1 and an optional unit like `3:ns`.
+fn parse_column(s: &str) -> Result<(usize, Option<Unit>), String> {
+    let error = || format!("expected a field number and an optional unit like `3:ns`, found `{s}`");
+    let (column, unit) = match s.split_once(':') {
+        Some((column, unit)) => (
+            column,
+            Some(Unit::from_str(unit, false).map_err(|_| error())?),
+        ),
+        None => (s, None),
+    };
+    match column.parse() {
+        Ok(column) if column > 0 => Ok((column, unit)),
+        _ => Err(error()),
+    }
+}
+
+/// Parses a positive duration like `1m` to nanoseconds.
+fn parse_interval(s: &str) -> Result<i64, String> {
+    match duration::parse(s)? {
+        0 => Err(format!("expected a positive interval, found `{s}`")),
+        nanos => Ok(nanos),
+    }
+}
+
+fn parse_wrap(s: &str) -> Result<(char, char), String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(open), Some(close), None) => Ok((open, close)),
+        _ => Err(format!(
+            "expected an opening and a closing character like `[]`, found `{s}`"
+        )),
+    }
+}
+
+fn parse_tz_mapping(s: &str) -> Result<(String, Zone), String> {
+    let (pattern, zone) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `PATTERN=ZONE`, found `{s}`"))?;
+    Ok((pattern.to_owned(), parse_zone(zone)?))
+}
+
+/// Matches `text` against a glob `pattern` supporting `*` and `?` wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was matched at
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => {
+                let Some((star_p, star_t)) = backtrack else {
+                    return false;
+                };
+                // Let the last `*` consume one more character
+                p = star_p + 1;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            }
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Julian {
+    /// Julian days, e.g. `2460369.5`
+    Jd,
+    /// Modified Julian days, e.g. `60369`
+    Mjd,
+}
+
+/// A count of days, with any time of day as a fraction, since an epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DayCount {
+    /// Excel serial dates: days since 1899-12-30.
+    Excel,
+    /// Julian days: days since noon on 4713-11-24 BC.
+    JulianDay,
+    /// Modified Julian days: days since 1858-11-17.
+    ModifiedJulianDay,
+}
+
+impl DayCount {
+    const SECONDS_PER_DAY: f64 = 86_400.0;
+
+    /// The value of the UNIX epoch in this day count.
+    const fn unix_epoch(self) -> f64 {
+        match self {
+            DayCount::Excel => 25_569.0,
+            DayCount::JulianDay => 2_440_587.5,
+            DayCount::ModifiedJulianDay => 40_587.0,
+        }
+    }
+
+    /// Whether whole numbers are detected as dates. Otherwise only values with a
+    /// fractional time of day are.
+    const fn detects_integers(self) -> bool {
+        !matches!(self, DayCount::Excel)
+    }
+
+    fn unix_seconds_to_days(self, s: i64) -> f64 {
+        s as f64 / Self::SECONDS_PER_DAY + self.unix_epoch()
+    }
+
+    /// Converts `days` to nanoseconds since the UNIX epoch, rounded to the second.
+    fn to_nanos(self, days: f64) -> Option<i64> {
+        let s = ((days - self.unix_epoch()) * Self::SECONDS_PER_DAY).round();
+        // Float to int `as` casts saturate, so reject values near the limits
+        (s.abs() < (i64::MAX / 1_000_000_000) as f64).then(|| s as i64 * 1_000_000_000)
+    }
+}
+
+/// Returns the key of the `key=value` pair or JSON `"key": value` member whose value,
+/// optionally quoted, starts at `start` in `line`.
+fn kv_key(line: &str, start: usize) -> Option<&str> {
+    let bytes = line.as_bytes();
+    let mut end = start;
+    if end > 0 && bytes[end - 1] == b'"' {
+        end -= 1;
+    }
+    let value_start = end;
+    while end > 0 && bytes[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    if end > 1 && bytes[end - 1] == b':' && bytes[end - 2] == b'"' {
+        let key_end = end - 2;
+        let key_start = bytes[..key_end].iter().rposition(|&b| b == b'"')? + 1;
+        return (key_start < key_end).then(|| &line[key_start..key_end]);
+    }
+    end = value_start.checked_sub(1).filter(|&i| bytes[i] == b'=')?;
+    let key_start = bytes[..end]
+        .iter()
+        .rposition(|b| b.is_ascii_whitespace() || matches!(b, b'"' | b'='))
+        .map_or(0, |i| i + 1);
+    (key_start < end).then(|| &line[key_start..end])
+}
+
+/// Returns the number, starting from 1, of the comma-separated field of `line` that
+/// contains `start`. Commas in double quotes don't separate fields.
+fn csv_field(line: &str, start: usize) -> usize {
+    let mut in_quotes = false;
+    let mut field = 1;
+    for &b in &line.as_bytes()[..start] {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => field += 1,
+            _ => {}
+        }
+    }
+    field
+}
+
+/// Returns `true` if `range` overlaps any of the ranges of `replacements`.
+fn overlaps(replacements: &[(Range<usize>, String)], range: &Range<usize>) -> bool {
+    replacements
+        .iter()
+        .any(|(r, _)| r.start < range.end && range.start < r.end)
 }
 
 #[derive(Debug)]
 struct Reformatter {
     min_len: usize,
-    bound_s: Range<i64>,
-    bound_ms: Range<i64>,
-    bound_ns: Range<i64>,
-    localize: bool,
-    quote: bool,
+    max_len: usize,
+    detector: Detector,
+    /// Bounds of day counts, which are detected in fractional values.
+    day_bounds: Vec<(DayCount, Range<f64>)>,
+    strict: bool,
+    /// The delimiters to mark timestamps with instead of converting them.
+    dry_run: Option<(&'static str, &'static str)>,
+    /// The delimiters to mark converted timestamps with.
+    highlight: Option<(&'static str, &'static str)>,
+    verbose: bool,
+    /// The number of the line being converted, for logging.
+    line_no: Cell<usize>,
+    /// The offset in the line being converted of the text being converted, when a long
+    /// line is converted in pieces.
+    line_offset: Cell<usize>,
+    max_per_line: Option<usize>,
+    occurrence: Option<usize>,
+    /// The number of timestamps detected so far in the line being converted.
+    detected: Cell<usize>,
+    /// Whether the date being converted is a wall-clock time skipped when clocks were
+    /// set forward.
+    nonexistent: Cell<bool>,
+    /// Glob patterns of the keys of `key=value` pairs whose values to convert. When
+    /// empty, timestamps are converted anywhere.
+    kv_keys: Vec<String>,
+    /// The fields of comma-separated lines to convert, with a detector for those in a
+    /// given unit. When empty, timestamps are converted anywhere.
+    columns: Vec<(usize, Option<Detector>)>,
+    /// Whether only numbers with a fractional part are detected.
+    fractions_only: bool,
+    zone: Zone,
+    renderer: style::Renderer,
+    /// The precision to render timestamps with instead of that of their unit. Dates
+    /// keep theirs.
+    precision: Option<Precision>,
+    /// The interval in nanoseconds to floor timestamps to before rendering them.
+    truncate_to: Option<i64>,
+    dst_policy: DstPolicy,
+    /// Whether to render timestamps in UTC followed by their time of day in `zone`.
+    both: bool,
+    /// Zones to also render timestamps in after `zone`, separated by `zone_separator`.
+    other_zones: Vec<Zone>,
+    zone_separator: String,
+    fiscal: Vec<Fiscal>,
+    sessions: Vec<session::Venue>,
+    /// Weekends and holidays to flag timestamps on, if enabled.
+    holidays: Option<holiday::Holidays>,
+    calendar: Calendar,
+    decode_base64: Option<Base64Mode>,
+    uuid: Option<IdMode>,
+    snowflake: Option<(IdMode, ids::Snowflakes)>,
+    /// How to convert ObjectIds created within the window, in seconds since the UNIX
+    /// epoch.
+    object_id: Option<(IdMode, Range<i64>)>,
+    ulid: Option<(IdMode, Range<i64>)>,
+    ksuid: Option<(IdMode, Range<i64>)>,
+    jwt: bool,
+    rewriter: Option<rewrite::Rewriter>,
+    #[cfg(feature = "plugin")]
+    plugins: Vec<plugin::Plugin>,
+    fixed_width: bool,
+    /// The width to pad formatted timestamps to, or `Some(None)` for the width of the
+    /// most precise detectable unit.
+    pad: Option<Option<usize>>,
+    scrubber: Option<scrub::Scrubber>,
+    to_unit: Option<Unit>,
+    /// Seconds since the UNIX epoch of the zero point of `to_unit`.
+    epoch_base: i64,
+    /// Converts detected timestamps to UTC unless they're already in it.
+    time_scale: Option<leap::TimeScale>,
+    durations: bool,
+    /// The number of timestamps converted so far.
+    conversions: Cell<usize>,
+    reformat_iso: bool,
+    syslog: Option<syslog::Syslog>,
+    access_log: bool,
+    /// The window to detect compact dates within, in seconds since the UNIX epoch.
+    compact_dates: Option<Range<i64>>,
+    #[cfg(feature = "protobuf")]
+    proto: Option<proto::Decoder>,
+    /// The characters to wrap formatted timestamps between.
+    wrap: Option<(char, char)>,
+    template: Option<template::Template>,
 }
 
 impl Reformatter {
-    fn new(threshold_years: i32, localize: bool, quote: bool) -> Self {
-        // This is only used as a (generous) heuristic, so it's OK to approximate here
-        let dt = chrono::Duration::days(threshold_years.abs() as i64 * 365);
-        let now = chrono::offset::Utc::now();
-        let upper_s: i64 = (now + dt).timestamp();
-        let lower_s: i64 = (now - dt).timestamp();
-        let bound_s = lower_s..upper_s;
-        let bound_ms = lower_s * 1_000..upper_s * 1_000;
-        let bound_ns = lower_s * 1_000_000_000..upper_s * 1_000_000_000;
-
-        Reformatter {
-            min_len: format!("{lower_s}").len(),
-            bound_s,
-            bound_ms,
-            bound_ns,
-            localize,
-            quote,
+    fn new(args: &Args) -> anyhow::Result<Self> {
+        let (lower_s, upper_s) = if args.no_threshold || args.threshold == 0 {
+            if args.unit.is_none() {
+                bail!("disabling the threshold requires `--unit`");
+            }
+            (i64::MIN, i64::MAX)
+        } else {
+            // This is only used as a (generous) heuristic, so it's OK to approximate here
+            let years = |years: Option<u32>| {
+                chrono::Duration::days(years.unwrap_or(args.threshold.unsigned_abs()) as i64 * 365)
+            };
+            let now = chrono::offset::Utc::now();
+            let zone = Self::zone(args);
+            (
+                args.min_date.map_or_else(
+                    || (now - years(args.past_years)).timestamp(),
+                    |date| date.resolve(zone, now),
+                ),
+                args.max_date.map_or_else(
+                    || (now + years(args.future_years)).timestamp(),
+                    |date| date.resolve(zone, now),
+                ),
+            )
+        };
+        let units = match args.unit {
+            Some(unit) => vec![unit],
+            None => Unit::DETECTED.to_vec(),
+        };
+        let mut units: Vec<_> = units
+            .into_iter()
+            .chain(args.filetime.then_some(Unit::FileTime))
+            .chain(args.dotnet_ticks.then_some(Unit::DotNetTicks))
+            .chain(
+                args.coarse_units
+                    .then_some(Unit::COARSE)
+                    .into_iter()
+                    .flatten(),
+            )
+            .collect();
+        // Earlier units take precedence
+        if let Some(preferred) = args.prefer_unit {
+            units.sort_by_key(|&unit| unit != preferred);
+        }
+        let detector = Detector::with_epoch_base(units, lower_s, upper_s, args.epoch_base)
+            .allow_negative(args.allow_negative)
+            .word_boundary(args.word_boundary);
+        let day_bounds: Vec<_> = args
+            .excel
+            .then_some(DayCount::Excel)
+            .into_iter()
+            .chain(args.julian.map(|julian| match julian {
+                Julian::Jd => DayCount::JulianDay,
+                Julian::Mjd => DayCount::ModifiedJulianDay,
+            }))
+            .map(|day_count| {
+                (
+                    day_count,
+                    day_count.unix_seconds_to_days(lower_s)
+                        ..day_count.unix_seconds_to_days(upper_s),
+                )
+            })
+            .collect();
+        let integer_bounds: Vec<Range<i64>> = detector
+            .bounds()
+            .map(|(_, b)| b.clone())
+            .chain(
+                day_bounds
+                    .iter()
+                    .filter(|(day_count, _)| day_count.detects_integers())
+                    .map(|(_, b)| b.start.ceil() as i64..b.end.ceil() as i64),
+            )
+            .collect();
+        let (min_len, max_len) = epoch_to::digit_range(integer_bounds.iter()).into_inner();
+
+        Ok(Reformatter {
+            min_len: args.min_digits.unwrap_or(min_len),
+            max_len: args.max_digits.unwrap_or(max_len),
+            detector,
+            day_bounds,
+            strict: args.strict,
+            dry_run: args.dry_run.then(|| {
+                if args.output.is_none() && stdout().is_terminal() {
+                    ("\x1b[1;33m", "\x1b[0m")
+                } else {
+                    (">>>", "<<<")
+                }
+            }),
+            highlight: None,
+            verbose: args.verbose,
+            line_no: Cell::new(0),
+            line_offset: Cell::new(0),
+            max_per_line: args.max_per_line,
+            occurrence: args.occurrence,
+            detected: Cell::new(0),
+            nonexistent: Cell::new(false),
+            kv_keys: if args.kv {
+                args.keys.clone()
+            } else {
+                Vec::new()
+            },
+            columns: args
+                .columns
+                .iter()
+                .map(|&(column, unit)| {
+                    let detector = unit.map(|unit| {
+                        Detector::with_epoch_base([unit], lower_s, upper_s, args.epoch_base)
+                            .allow_negative(args.allow_negative)
+                    });
+                    (column, detector)
+                })
+                .collect(),
+            fractions_only: args.fractions_only,
+            zone: Self::zone(args),
+            renderer: style::Renderer {
+                style: args.style,
+                format: args.format.clone(),
+                locale: args.locale.unwrap_or(Locale::POSIX),
+            },
+            precision: args.precision.precision(),
+            truncate_to: args.truncate_to,
+            dst_policy: args.dst_policy,
+            both: args.both,
+            other_zones: args.tz.iter().skip(1).copied().collect(),
+            zone_separator: args.tz_separator.clone(),
+            fiscal: args.fiscal.clone(),
+            sessions: args.session.clone(),
+            holidays: match (&args.holidays, args.annotate_calendar) {
+                (Some(path), _) => Some(holiday::Holidays::load(path)?),
+                (None, true) => Some(holiday::Holidays::default()),
+                (None, false) => None,
+            },
+            calendar: Calendar::new(args.fiscal_year_start, &args.contract_cycle),
+            decode_base64: args.decode_base64,
+            uuid: args.uuid,
+            snowflake: args.snowflake.map(|mode| {
+                let snowflakes = ids::Snowflakes {
+                    epoch_ms: args.snowflake_epoch,
+                    window: lower_s..upper_s,
+                };
+                (mode, snowflakes)
+            }),
+            object_id: args.objectid.map(|mode| (mode, lower_s..upper_s)),
+            ulid: args.ulid.map(|mode| (mode, lower_s..upper_s)),
+            ksuid: args.ksuid.map(|mode| (mode, lower_s..upper_s)),
+            jwt: args.jwt,
+            rewriter: args
+                .rewrite
+                .as_deref()
+                .zip(args.with.as_deref())
+                .map(|(regex, template)| rewrite::Rewriter::new(regex, template))
+                .transpose()?,
+            #[cfg(feature = "plugin")]
+            plugins: args
+                .plugin
+                .iter()
+                .map(|path| plugin::Plugin::load(path))
+                .collect::<anyhow::Result<_>>()?,
+            #[cfg(feature = "protobuf")]
+            proto: args
+                .proto_descriptor
+                .as_deref()
+                .zip(args.proto_type.as_deref())
+                .map(|(descriptor, message)| proto::Decoder::new(descriptor, message))
+                .transpose()?,
+            fixed_width: args.fixed_width,
+            pad: args.pad,
+            to_unit: args.to_unit,
+            epoch_base: args.epoch_base,
+            time_scale: match (args.scale, &args.leap_seconds) {
+                (leap::Scale::Utc, _) => None,
+                (scale, Some(path)) => Some(leap::TimeScale::load(scale, path)?),
+                (scale, None) => Some(leap::TimeScale::new(scale)),
+            },
+            scrubber: args
+                .scrub
+                .clone()
+                .map(|placeholder| {
+                    let scrubber = scrub::Scrubber::new(placeholder);
+                    if args.map_file.is_some() {
+                        scrubber.check_reversible()?;
+                    }
+                    anyhow::Ok(scrubber)
+                })
+                .transpose()?,
+            durations: args.durations,
+            conversions: Cell::new(0),
+            reformat_iso: args.reformat_iso,
+            syslog: args.syslog.then(|| syslog::Syslog::new(lower_s..upper_s)),
+            access_log: args.access_log,
+            compact_dates: args.compact_dates.then_some(lower_s..upper_s),
+            wrap: args
+                .wrap
+                .or(args.quote_char.map(|c| (c, c)))
+                .or(args.quote.then_some(('"', '"'))),
+            template: args
+                .template
+                .as_deref()
+                .map(template::Template::new)
+                .transpose()?,
+        })
+    }
+
+    fn zone(args: &Args) -> Zone {
+        let mapped = args.input.as_deref().and_then(|input| {
+            let name = input.file_name().unwrap_or_default().to_string_lossy();
+            let path = input.to_string_lossy();
+            args.tz_map
+                .iter()
+                .find(|(pattern, _)| glob_match(pattern, &name) || glob_match(pattern, &path))
+                .map(|(_, zone)| *zone)
+        });
+        match (mapped, args.tz.first().copied()) {
+            (Some(zone), _) | (None, Some(zone)) => zone,
+            (None, None) if args.local => Zone::Local,
+            (None, None) => Zone::Utc,
         }
     }
 
     fn write<T: Write>(&self, writer: &mut T, line: &str) -> anyhow::Result<()> {
-        const NUMBERS: RangeInclusive<char> = '0'..='9';
-        // let line = line.as_bytes();
-        let mut text_iter = line.char_indices().peekable();
-        while let Some((text_start, _c)) = text_iter.peek() {
-            let text_start = *text_start;
-            // Otherwise, no timestamp found
-            let Some((number_start, _)) = text_iter.find(|(_, c)| NUMBERS.contains(c)) else {
-                write!(writer, "{}", &line[text_start..])?;
-                break;
+        self.write_replaced(writer, line, &self.replacements(line)?)
+    }
+
+    /// Writes `line` with `replacements`, as returned by [`Self::replacements`], and its
+    /// timestamps converted.
+    fn write_replaced<T: Write>(
+        &self,
+        writer: &mut T,
+        line: &str,
+        replacements: &[(Range<usize>, String)],
+    ) -> anyhow::Result<()> {
+        let mut text_start = 0;
+        for (range, replacement) in replacements {
+            self.write_text(writer, &line[text_start..range.start], text_start)?;
+            if let Some((open, close)) = self.dry_run {
+                write!(writer, "{open}{}{close}", &line[range.clone()])?;
+            } else {
+                writer.write_all(replacement.as_bytes())?;
+            }
+            text_start = range.end;
+        }
+        self.write_text(writer, &line[text_start..], text_start)
+    }
+
+    /// Writes `line`, which isn't valid UTF-8, with any timestamps in its valid parts
+    /// converted and the rest unchanged.
+    fn write_bytes<T: Write>(&self, writer: &mut T, line: &[u8]) -> anyhow::Result<()> {
+        // Timestamps are ASCII, so invalid bytes can only separate them
+        for chunk in line.utf8_chunks() {
+            self.write(writer, chunk.valid())?;
+            writer.write_all(chunk.invalid())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the byte ranges of the rewrites, encoded payloads, textual dates, and
+    /// durations in `line` to replace, along with their replacements, in order.
+    fn replacements(&self, line: &str) -> anyhow::Result<Vec<(Range<usize>, String)>> {
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        if let Some(rewriter) = &self.rewriter {
+            replacements.extend(rewriter.rewrites(line, |group, offset| {
+                self.without_counting(|| {
+                    let mut converted = Vec::new();
+                    self.write_text(&mut converted, group, offset)?;
+                    // Only timestamps are replaced, so the text remains valid UTF-8
+                    Ok(String::from_utf8(converted).expect("valid UTF-8"))
+                })
+            })?);
+        }
+        #[cfg(feature = "plugin")]
+        for plugin in &self.plugins {
+            let ids = plugin.detect(line)?;
+            self.convert_ids(&mut replacements, line, ids.into_iter(), IdMode::Replace)?;
+        }
+        #[cfg(feature = "protobuf")]
+        if let Some(decoder) = &self.proto {
+            for (range, json) in decoder.payloads(line) {
+                if !overlaps(&replacements, &range) {
+                    let converted = self.without_counting(|| self.convert(&json))?;
+                    replacements.push((range, converted));
+                }
+            }
+        }
+        if self.jwt {
+            for (range, claims) in encoded::jwt_claims(line) {
+                if overlaps(&replacements, &range) {
+                    continue;
+                }
+                let claims: Vec<_> = claims
+                    .into_iter()
+                    .map(|(claim, seconds)| {
+                        let time_ns = (seconds * 1e9).round() as i64;
+                        let precision = if seconds.fract() == 0.0 {
+                            Precision::Seconds
+                        } else {
+                            Precision::Millis
+                        };
+                        format!("{claim}: {}", self.format_time(time_ns, precision))
+                    })
+                    .collect();
+                let replacement = format!("{} ({})", &line[range.clone()], claims.join(", "));
+                replacements.push((range, replacement));
+            }
+        }
+        if let Some(mode) = self.decode_base64 {
+            for payload in encoded::payloads(line) {
+                let Some(text) = payload.text() else {
+                    continue;
+                };
+                if overlaps(&replacements, &payload.range) {
+                    continue;
+                }
+                let converted = self.without_counting(|| self.convert(text))?;
+                if converted == text {
+                    continue;
+                }
+                let replacement = match mode {
+                    Base64Mode::Reencode => payload.encode(&converted),
+                    Base64Mode::Annotate => format!(
+                        "{} (decoded: {})",
+                        &line[payload.range.clone()],
+                        converted.replace('\n', "\\n")
+                    ),
+                };
+                replacements.push((payload.range, replacement));
+            }
+        }
+        if let Some(mode) = self.uuid {
+            self.convert_ids(&mut replacements, line, ids::uuids(line), mode)?;
+        }
+        if let Some((mode, snowflakes)) = &self.snowflake {
+            self.convert_ids(&mut replacements, line, snowflakes.find(line), *mode)?;
+        }
+        if let Some((mode, window)) = &self.object_id {
+            let object_ids = ids::object_ids(line, window.clone());
+            self.convert_ids(&mut replacements, line, object_ids, *mode)?;
+        }
+        if let Some((mode, window)) = &self.ulid {
+            let ulids = ids::ulids(line, window.clone());
+            self.convert_ids(&mut replacements, line, ulids, *mode)?;
+        }
+        if let Some((mode, window)) = &self.ksuid {
+            let ksuids = ids::ksuids(line, window.clone());
+            self.convert_ids(&mut replacements, line, ksuids, *mode)?;
+        }
+        if self.reformat_iso {
+            for (range, time, precision) in iso::timestamps(line) {
+                if !overlaps(&replacements, &range) {
+                    let converted = self.convert_date(line, range.clone(), time, precision)?;
+                    replacements.extend(converted.map(|converted| (range, converted)));
+                }
+            }
+        }
+        if let Some(syslog) = &self.syslog {
+            let dates = syslog.dates(line, |wall_time| {
+                self.zone.resolve(wall_time, self.dst_policy)
+            });
+            for (range, (time, nonexistent)) in dates {
+                if !overlaps(&replacements, &range) {
+                    let converted = self.convert_wall_time(
+                        line,
+                        range.clone(),
+                        time,
+                        Precision::Seconds,
+                        nonexistent,
+                    )?;
+                    replacements.extend(converted.map(|converted| (range, converted)));
+                }
+            }
+        }
+        if self.access_log {
+            for (range, time) in access_log::dates(line) {
+                if !overlaps(&replacements, &range) {
+                    let converted =
+                        self.convert_date(line, range.clone(), time, Precision::Seconds)?;
+                    replacements.extend(converted.map(|converted| (range, converted)));
+                }
+            }
+        }
+        if let Some(window) = &self.compact_dates {
+            let dates = compact::dates(line, window.clone(), |wall_time| {
+                self.zone.resolve(wall_time, self.dst_policy)
+            });
+            for (range, (time, nonexistent), precision) in dates {
+                if !overlaps(&replacements, &range) {
+                    let converted =
+                        self.convert_wall_time(line, range.clone(), time, precision, nonexistent)?;
+                    replacements.extend(converted.map(|converted| (range, converted)));
+                }
+            }
+        }
+        if self.durations {
+            for (range, duration) in duration::durations(line) {
+                if !overlaps(&replacements, &range) {
+                    replacements.push((range, duration));
+                }
+            }
+        }
+        replacements.sort_by_key(|(range, _)| range.start);
+        Ok(replacements)
+    }
+
+    /// Returns the textual date `time` at `range` in `line` rendered with `precision` like
+    /// any converted timestamp, or `None` if it's out of range.
+    fn convert_date(
+        &self,
+        line: &str,
+        range: Range<usize>,
+        time: DateTime<Utc>,
+        precision: Precision,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(time_ns) = time.timestamp_nanos_opt() else {
+            return Ok(None);
+        };
+        let m = Match {
+            range,
+            time_ns,
+            precision,
+        };
+        let mut converted = Vec::new();
+        self.write_conversion(&mut converted, line, 0, &m)?;
+        // Formatted timestamps are always valid UTF-8
+        Ok(Some(String::from_utf8(converted).expect("valid UTF-8")))
+    }
+
+    /// Like [`Self::convert_date`], flagging times that are `nonexistent` wall-clock
+    /// times.
+    fn convert_wall_time(
+        &self,
+        line: &str,
+        range: Range<usize>,
+        time: DateTime<Utc>,
+        precision: Precision,
+        nonexistent: bool,
+    ) -> anyhow::Result<Option<String>> {
+        self.nonexistent.set(nonexistent);
+        let converted = self.convert_date(line, range, time, precision);
+        self.nonexistent.set(false);
+        converted
+    }
+
+    /// Adds the replacements of `ids` in `line` that don't overlap others in `mode`.
+    fn convert_ids(
+        &self,
+        replacements: &mut Vec<(Range<usize>, String)>,
+        line: &str,
+        ids: impl Iterator<Item = ids::Id>,
+        mode: IdMode,
+    ) -> anyhow::Result<()> {
+        for (range, time, precision) in ids {
+            if overlaps(replacements, &range) {
+                continue;
+            }
+            let Some(converted) = self.convert_date(line, range.clone(), time, precision)? else {
+                continue;
             };
-            // Find index of first non-number character after `number_start`. We know this character
-            // isn't a number, so print it as `text_after`
-            let (number_end, text_after) = text_iter
-                .find(|(_, c)| !NUMBERS.contains(c))
-                .map(|(i, _)| (i, &line[i..i + 1]))
-                .unwrap_or_else(|| (line.len(), ""));
-
-            // If the length of the number is less than that of the lower second bound, can skip parsing
-            if (number_end - number_start) >= self.min_len {
-                let number: &str = &line[number_start..number_end];
-                let parse_result = number.parse().ok().and_then(|n| {
-                    if self.bound_s.contains(&n) {
-                        Some((n * 1_000_000_000, SecondsFormat::Secs))
-                    } else if self.bound_ms.contains(&n) {
-                        Some((n * 1_000_000, SecondsFormat::Millis))
-                    } else if self.bound_ns.contains(&n) {
-                        Some((n, SecondsFormat::Nanos))
-                    } else {
-                        None
+            let replacement = match mode {
+                IdMode::Annotate => format!("{} ({converted})", &line[range.clone()]),
+                IdMode::Replace => converted,
+            };
+            replacements.push((range, replacement));
+        }
+        Ok(())
+    }
+
+    /// Returns `text` with any timestamps converted.
+    fn convert(&self, text: &str) -> anyhow::Result<String> {
+        let mut converted = Vec::new();
+        self.write(&mut converted, text)?;
+        // Only timestamps are replaced, so the text remains valid UTF-8
+        Ok(String::from_utf8(converted).expect("valid UTF-8"))
+    }
+
+    /// Writes `text`, which starts at byte `offset` of its line, with any timestamps
+    /// converted.
+    fn write_text<T: Write>(
+        &self,
+        writer: &mut T,
+        text: &str,
+        offset: usize,
+    ) -> anyhow::Result<()> {
+        let mut text_start = 0;
+        for m in self.matches(text) {
+            write!(writer, "{}", &text[text_start..m.range.start])?;
+            self.write_conversion(writer, text, offset, &m)?;
+            text_start = m.range.end;
+        }
+        write!(writer, "{}", &text[text_start..])?;
+        Ok(())
+    }
+
+    /// Writes only the timestamps found in `line`, one per line.
+    fn write_extracted<T: Write>(
+        &self,
+        writer: &mut T,
+        line_no: usize,
+        line: &str,
+        with_offset: bool,
+    ) -> anyhow::Result<()> {
+        for m in self.matches(line) {
+            if with_offset {
+                write!(
+                    writer,
+                    "{line_no}:{}:",
+                    self.line_offset.get() + m.range.start
+                )?;
+            }
+            self.write_conversion(writer, line, 0, &m)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the first timestamp found in `line`, its conversion, and the rest of the
+    /// line as tab-separated values. Lines without timestamps only have the last
+    /// field.
+    fn write_tsv<T: Write>(&self, writer: &mut T, line: &str) -> anyhow::Result<()> {
+        let Some(m) = self.matches(line).next() else {
+            writeln!(writer, "\t\t{line}")?;
+            return Ok(());
+        };
+        write!(writer, "{}\t", &line[m.range.clone()])?;
+        self.write_conversion(writer, line, 0, &m)?;
+        // Join the text around the timestamp with a single space
+        let before = line[..m.range.start].trim_end();
+        let after = line[m.range.end..].trim_start();
+        let space = if before.is_empty() || after.is_empty() {
+            ""
+        } else {
+            " "
+        };
+        writeln!(writer, "\t{before}{space}{after}")?;
+        Ok(())
+    }
+
+    /// Writes a JSON object describing each timestamp in `line` on its own line.
+    fn write_jsonl<T: Write>(
+        &self,
+        writer: &mut T,
+        line_no: usize,
+        line: &str,
+    ) -> anyhow::Result<()> {
+        let offset = self.line_offset.get();
+        for m in self.matches(line) {
+            let original = &line[m.range.clone()];
+            if self.strict {
+                self.check_unambiguous(original)?;
+            }
+            self.conversions.set(self.conversions.get() + 1);
+            let object = serde_json::json!({
+                "line_no": line_no,
+                "byte_range": [offset + m.range.start, offset + m.range.end],
+                "original": original,
+                "unit": self.unit_name(original, m.precision),
+                "epoch_ns": m.time_ns,
+                "iso": epoch_to::format_utc(m.time_ns, m.precision),
+            });
+            serde_json::to_writer(&mut *writer, &object)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Starts converting line `line_no`, which resets the count of timestamps in it.
+    fn start_line(&self, line_no: usize) {
+        self.line_no.set(line_no);
+        self.line_offset.set(0);
+        self.detected.set(0);
+    }
+
+    /// Continues converting the current line with the text `offset` bytes into it.
+    fn continue_line(&self, offset: usize) {
+        self.line_offset.set(offset);
+    }
+
+    /// Checks that `converted` only differs from `line` in the detected timestamps and
+    /// `replacements` it was converted with, and that converted timestamps parse back to
+    /// their original time.
+    fn verify_passthrough(
+        &self,
+        line: &str,
+        replacements: &[(Range<usize>, String)],
+        converted: &str,
+    ) -> anyhow::Result<()> {
+        // Detect the same timestamps as the conversion did
+        self.start_line(self.line_no.get());
+        let mut rest = converted;
+        let mut text_start = 0;
+        for (range, replacement) in replacements {
+            rest = self.verify_text(&line[text_start..range.start], text_start, rest)?;
+            rest = rest
+                .strip_prefix(replacement.as_str())
+                .with_context(|| format!("replacement at byte {} was altered", range.start))?;
+            text_start = range.end;
+        }
+        rest = self.verify_text(&line[text_start..], text_start, rest)?;
+        if !rest.is_empty() {
+            bail!("unexpected trailing output `{rest}`");
+        }
+        Ok(())
+    }
+
+    /// Checks that `converted` starts with `text`, which starts at byte `offset` of its
+    /// line, with only its timestamps converted. Returns the rest of `converted`.
+    fn verify_text<'a>(
+        &self,
+        text: &str,
+        offset: usize,
+        mut converted: &'a str,
+    ) -> anyhow::Result<&'a str> {
+        let mut text_start = 0;
+        for m in self.matches(text) {
+            converted = converted
+                .strip_prefix(&text[text_start..m.range.start])
+                .with_context(|| format!("text at byte {} was altered", offset + text_start))?;
+            let mut time = Vec::new();
+            self.write_time(&mut time, text, &m)?;
+            // Formatted timestamps are always valid UTF-8
+            let time = String::from_utf8(time).expect("valid UTF-8");
+            converted = converted.strip_prefix(time.as_str()).with_context(|| {
+                format!("timestamp at byte {} was altered", offset + m.range.start)
+            })?;
+            // Placeholders and truncated timestamps can't be converted back
+            let is_truncated = self.fixed_width
+                || self.pad.is_some()
+                || self
+                    .precision
+                    .is_some_and(|precision| precision < m.precision);
+            if self.scrubber.is_none() && !is_truncated {
+                let unwrapped = self
+                    .wrap
+                    .and_then(|(open, close)| time.strip_prefix(open)?.strip_suffix(close))
+                    .unwrap_or(&time);
+                if let Some(time_ns) = parse_rendered(unwrapped, m.precision) {
+                    if time_ns != m.time_ns {
+                        bail!(
+                            "timestamp at byte {} doesn't round trip: `{}` became `{time}`",
+                            offset + m.range.start,
+                            &text[m.range.clone()],
+                        );
                     }
-                });
-                if let Some((time_ns, sec_fmt)) = parse_result {
-                    let time = chrono::Utc.timestamp_nanos(time_ns);
-                    let text_before = &line[text_start..number_start];
-                    let time = if self.localize {
-                        DateTime::<Local>::from(time).format(Self::rfc_format::<true>(sec_fmt))
-                    } else {
-                        time.format(Self::rfc_format::<false>(sec_fmt))
-                    };
-                    if self.quote {
-                        write!(writer, "{text_before}\"{time}\"{text_after}")
-                    } else {
-                        write!(writer, "{text_before}{time}{text_after}")
-                    }?;
-                    continue;
                 }
             }
-            // plus 1 for text_after
-            let text = &line[text_start..(number_end + 1).min(line.len())];
-            write!(writer, "{text}",)?;
+            text_start = m.range.end;
         }
+        converted
+            .strip_prefix(&text[text_start..])
+            .with_context(|| format!("text at byte {} was altered", offset + text_start))
+    }
+
+    /// Writes the replacement of the timestamp `m` in `text`, which starts at byte
+    /// `offset` of its line, checking and logging the conversion as configured.
+    fn write_conversion<T: Write>(
+        &self,
+        writer: &mut T,
+        text: &str,
+        offset: usize,
+        m: &Match,
+    ) -> anyhow::Result<()> {
+        let number = &text[m.range.clone()];
+        if self.strict {
+            self.check_unambiguous(number)?;
+        }
+        self.conversions.set(self.conversions.get() + 1);
+        if let Some((open, close)) = self.dry_run {
+            write!(writer, "{open}{number}{close}")?;
+            return Ok(());
+        }
+        let (open, close) = self.highlight.unwrap_or_default();
+        write!(writer, "{open}")?;
+        if self.verbose {
+            let offset = self.line_offset.get() + offset;
+            let mut time = Vec::new();
+            self.write_time(&mut time, text, m)?;
+            // Formatted timestamps are always valid UTF-8
+            let time = String::from_utf8(time).expect("valid UTF-8");
+            let others = self.other_unit_names(number);
+            let others = if others.is_empty() {
+                String::new()
+            } else {
+                format!(" (also in range as {})", others.join(", "))
+            };
+            eprintln!(
+                "line {}, bytes {}..{}: `{number}` as {}{others} -> `{time}`",
+                self.line_no.get(),
+                offset + m.range.start,
+                offset + m.range.end,
+                self.unit_name(number, m.precision),
+            );
+            writer.write_all(time.as_bytes())?;
+        } else {
+            self.write_time(writer, text, m)?;
+        }
+        write!(writer, "{close}")?;
         Ok(())
     }
 
-    const fn rfc_format<const LOCALIZE: bool>(sec_fmt: SecondsFormat) -> &'static str {
-        match (LOCALIZE, sec_fmt) {
-            (true, SecondsFormat::Secs) => "%Y-%m-%dT%H:%M:%S%Z",
-            (false, SecondsFormat::Secs) => "%Y-%m-%dT%H:%M:%SZ",
-            (true, SecondsFormat::Millis) => "%Y-%m-%dT%H:%M:%S%.3f%Z",
-            (false, SecondsFormat::Millis) => "%Y-%m-%dT%H:%M:%S%.3fZ",
-            (true, _) => "%Y-%m-%dT%H:%M:%S%.9f%Z",
-            (false, _) => "%Y-%m-%dT%H:%M:%S%.9fZ",
+    /// Fails if `number` is within the bounds of more than one unit, or only warns when
+    /// verbose.
+    fn check_unambiguous(&self, number: &str) -> anyhow::Result<()> {
+        let Ok(n) = number.parse() else {
+            return Ok(());
+        };
+        let units: Vec<_> = self
+            .detector
+            .units(n)
+            .filter_map(|unit| unit.to_possible_value())
+            .map(|unit| format!("`{}`", unit.get_name()))
+            .collect();
+        if units.len() > 1 {
+            let message = format!("`{number}` is ambiguous between units {}", units.join(", "));
+            if !self.verbose {
+                bail!(message);
+            }
+            eprintln!("warning: {message}");
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the units `number` could also have been detected in, which
+    /// the detected unit took precedence over.
+    fn other_unit_names(&self, number: &str) -> Vec<String> {
+        let Ok(n) = number.parse() else {
+            return Vec::new();
+        };
+        self.detector
+            .units(n)
+            .skip(1)
+            .filter_map(|unit| unit.to_possible_value())
+            .map(|unit| unit.get_name().to_owned())
+            .collect()
+    }
+
+    /// Returns the name of the unit `number` was detected in, as accepted by `--unit`,
+    /// `--julian`, or the name of the day count's flag.
+    fn unit_name(&self, number: &str, precision: Precision) -> String {
+        let integer = number
+            .split_once('.')
+            .map_or(number, |(integer, _)| integer);
+        if precision != Precision::Days && integer.len() < number.len() {
+            // Fractional seconds
+            if let Ok(n) = integer.parse() {
+                if self.detector.parse(n).is_some() {
+                    return "s".to_owned();
+                }
+            }
+        }
+        if let Ok(n) = number.parse::<i64>() {
+            let unit = self.detector.units(n).next();
+            // Negative numbers are detected with mirrored bounds
+            let unit = unit.or_else(|| self.detector.units(n.checked_neg()?).next());
+            if let Some(value) = unit.and_then(|unit| unit.to_possible_value()) {
+                return value.get_name().to_owned();
+            }
+        }
+        let Ok(days) = number.parse::<f64>() else {
+            return "date".to_owned();
+        };
+        match self.day_bounds.iter().find(|(_, b)| b.contains(&days)) {
+            Some((DayCount::Excel, _)) => "excel",
+            Some((DayCount::JulianDay, _)) => "jd",
+            Some((DayCount::ModifiedJulianDay, _)) => "mjd",
+            None => "date",
+        }
+        .to_owned()
+    }
+
+    /// Writes the replacement of the timestamp `m` in `line`.
+    fn write_time<T: Write>(&self, writer: &mut T, line: &str, m: &Match) -> anyhow::Result<()> {
+        if self.fixed_width {
+            let width = m.range.len();
+            let time = self.render_within(line, m, width, Precision::Days)?;
+            if time.chars().count() > width {
+                bail!(
+                    "`{}` is only {width} characters wide, too narrow for `{time}` with \
+                     `--fixed-width`",
+                    &line[m.range.clone()],
+                );
+            }
+            write!(writer, "{time:<width$}")?;
+            return Ok(());
+        }
+        if let Some(width) = self.pad {
+            return self.write_time_padded(writer, line, m, width);
+        }
+        self.write_time_unpadded(writer, line, m)
+    }
+
+    /// Writes the replacement of the timestamp `m` in `line` padded to `width`, or the
+    /// width with the most precise detectable unit, dropping fractional digits as needed
+    /// to fit.
+    fn write_time_padded<T: Write>(
+        &self,
+        writer: &mut T,
+        line: &str,
+        m: &Match,
+        width: Option<usize>,
+    ) -> anyhow::Result<()> {
+        let width = match width {
+            Some(width) => width,
+            None => {
+                let precision = self
+                    .detector
+                    .bounds()
+                    .map(|(unit, _)| unit.precision())
+                    .max()
+                    .unwrap_or(Precision::Seconds);
+                self.render_at(line, m, precision)?.chars().count()
+            }
+        };
+        let time = self.render_within(line, m, width, Precision::Seconds)?;
+        write!(writer, "{time:<width$}")?;
+        Ok(())
+    }
+
+    /// Renders the timestamp `m` in `line` with the highest precision down to
+    /// `min_precision` that fits in `width` characters, or with `min_precision` if none
+    /// do.
+    fn render_within(
+        &self,
+        line: &str,
+        m: &Match,
+        width: usize,
+        min_precision: Precision,
+    ) -> anyhow::Result<String> {
+        let mut precision = m.precision;
+        let mut time = self.render_at(line, m, precision)?;
+        while time.chars().count() > width && precision > min_precision {
+            precision = match precision {
+                Precision::Nanos => Precision::Micros,
+                Precision::Micros => Precision::Millis,
+                Precision::Millis => Precision::Seconds,
+                Precision::Seconds | Precision::Days => Precision::Days,
+            };
+            time = self.render_at(line, m, precision)?;
+        }
+        Ok(time)
+    }
+
+    /// Renders the timestamp `m` in `line` as if it were detected with `precision`.
+    fn render_at(&self, line: &str, m: &Match, precision: Precision) -> anyhow::Result<String> {
+        let mut time = Vec::new();
+        let m = Match {
+            range: m.range.clone(),
+            time_ns: m.time_ns,
+            precision,
+        };
+        self.write_time_unpadded(&mut time, line, &m)?;
+        // Formatted timestamps are always valid UTF-8
+        Ok(String::from_utf8(time).expect("valid UTF-8"))
+    }
+
+    fn write_time_unpadded<T: Write>(
+        &self,
+        writer: &mut T,
+        line: &str,
+        m: &Match,
+    ) -> anyhow::Result<()> {
+        let Some(template) = &self.template else {
+            return self.write_time_wrapped(writer, line, m);
+        };
+        let original = &line[m.range.clone()];
+        let expanded = template.expand(|placeholder| {
+            Ok(match placeholder {
+                Placeholder::Iso => {
+                    let mut time = Vec::new();
+                    self.write_time_wrapped(&mut time, line, m)?;
+                    // Formatted timestamps are always valid UTF-8
+                    String::from_utf8(time).expect("valid UTF-8")
+                }
+                Placeholder::Original => original.to_owned(),
+                Placeholder::Unit => self.unit_name(original, m.precision),
+                Placeholder::EpochS => m.time_ns.div_euclid(1_000_000_000).to_string(),
+                Placeholder::EpochNs => m.time_ns.to_string(),
+                Placeholder::Relative => {
+                    explain::relative(Utc.timestamp_nanos(m.time_ns), Utc::now())
+                }
+            })
+        })?;
+        writer.write_all(expanded.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_time_wrapped<T: Write>(
+        &self,
+        writer: &mut T,
+        line: &str,
+        m: &Match,
+    ) -> anyhow::Result<()> {
+        if let Some((open, _)) = self.wrap {
+            write!(writer, "{open}")?;
+        }
+        if let Some(scrubber) = &self.scrubber {
+            write!(writer, "{}", scrubber.placeholder(&line[m.range.clone()]))?;
+        } else if let Some(unit) = self.to_unit {
+            match unit.nanos_to_unit(self.truncate(m.time_ns), self.epoch_base) {
+                Some(n) => write!(writer, "{n}")?,
+                None => writer.write_all(line[m.range.clone()].as_bytes())?,
+            }
+        } else {
+            self.write_formatted(writer, m.time_ns, m.precision)?;
+        }
+        if let Some((_, close)) = self.wrap {
+            write!(writer, "{close}")?;
+        }
+        Ok(())
+    }
+
+    /// Formats a timestamp with any annotations, without quoting.
+    fn format_time(&self, time_ns: i64, precision: Precision) -> String {
+        self.conversions.set(self.conversions.get() + 1);
+        let mut formatted = Vec::new();
+        self.write_formatted(&mut formatted, time_ns, precision)
+            .expect("writing to a Vec can't fail");
+        // Formatted timestamps are always valid UTF-8
+        String::from_utf8(formatted).expect("valid UTF-8")
+    }
+
+    fn write_formatted<T: Write>(
+        &self,
+        writer: &mut T,
+        time_ns: i64,
+        precision: Precision,
+    ) -> anyhow::Result<()> {
+        let utc_time = Utc.timestamp_nanos(self.truncate(time_ns));
+        // Don't render digits that truncation zeroed
+        let precision = match self.truncate_to {
+            Some(interval) => precision.min(duration::precision(interval)),
+            None => precision,
+        };
+        let precision = match self.precision {
+            Some(precision_override) if precision != Precision::Days => precision_override,
+            _ => precision,
+        };
+        // Dates aren't localized, since they'd shift by a day in some zones
+        let zone = if precision == Precision::Days || self.both {
+            Zone::Utc
+        } else {
+            self.zone
+        };
+        write!(writer, "{}", self.render(utc_time, precision, zone))?;
+        if self.both && precision != Precision::Days {
+            let other_zone = match self.zone {
+                Zone::Utc => Zone::Local,
+                zone => zone,
+            };
+            write!(
+                writer,
+                " ({})",
+                self.render_time_of_day(utc_time, precision, other_zone)
+            )?;
+        }
+        if precision != Precision::Days {
+            for &zone in &self.other_zones {
+                write!(
+                    writer,
+                    "{}{}",
+                    self.zone_separator,
+                    self.render(utc_time, precision, zone)
+                )?;
+            }
+        }
+        if self.dst_policy == DstPolicy::Annotate && self.zone.is_ambiguous(utc_time) {
+            writer.write_all(b" (ambiguous)")?;
+        }
+        if self.nonexistent.get() {
+            writer.write_all(b" (nonexistent)")?;
+        }
+        if !self.fiscal.is_empty() {
+            let date = self.zone.wall_time(utc_time).date();
+            for &fiscal in &self.fiscal {
+                write!(writer, " ({})", self.calendar.label(fiscal, date))?;
+            }
+        }
+        for venue in &self.sessions {
+            write!(writer, " ({})", venue.label(utc_time))?;
+        }
+        if let Some(label) = self
+            .holidays
+            .as_ref()
+            .and_then(|holidays| holidays.label(self.zone.wall_time(utc_time).date()))
+        {
+            write!(writer, " ({label})")?;
+        }
+        Ok(())
+    }
+
+    /// Floors `time_ns` to the `--truncate-to` interval, if any.
+    fn truncate(&self, time_ns: i64) -> i64 {
+        match self.truncate_to {
+            Some(interval) => time_ns - time_ns.rem_euclid(interval),
+            None => time_ns,
+        }
+    }
+
+    /// Renders `time` in `zone` without any annotations.
+    fn render(&self, time: DateTime<Utc>, precision: Precision, zone: Zone) -> String {
+        match zone {
+            Zone::Utc => self.renderer.format(&time, precision, true),
+            Zone::Local => self
+                .renderer
+                .format(&DateTime::<Local>::from(time), precision, false),
+            Zone::Tz(tz) => self
+                .renderer
+                .format(&time.with_timezone(&tz), precision, false),
+        }
+    }
+
+    /// Renders the time of day of `time` in `zone` with the zone's abbreviation, and the
+    /// date too if it differs from the date in UTC.
+    fn render_time_of_day(&self, time: DateTime<Utc>, precision: Precision, zone: Zone) -> String {
+        let fraction = match precision {
+            Precision::Days | Precision::Seconds => "",
+            Precision::Millis => "%.3f",
+            Precision::Micros => "%.6f",
+            Precision::Nanos => "%.9f",
+        };
+        let date = if zone.wall_time(time).date() == time.date_naive() {
+            ""
+        } else {
+            "%Y-%m-%d "
+        };
+        let format = format!("{date}%H:%M:%S{fraction} %Z");
+        match zone {
+            Zone::Utc => time.format(&format).to_string(),
+            Zone::Local => time.with_timezone(&Local).format(&format).to_string(),
+            Zone::Tz(tz) => time.with_timezone(&tz).format(&format).to_string(),
+        }
+    }
+
+    /// Runs `convert` on text within the line, like a decoded payload, without counting
+    /// its timestamps towards `--max-per-line` and `--occurrence` of the line, which
+    /// instead apply to that text alone.
+    fn without_counting<T>(&self, convert: impl FnOnce() -> T) -> T {
+        let detected = self.detected.replace(0);
+        let converted = convert();
+        self.detected.set(detected);
+        converted
+    }
+
+    /// Returns the time of the first timestamp in `line` without counting it as detected.
+    fn first_time(&self, line: &str) -> Option<i64> {
+        let detected = self.detected.get();
+        let first = self.matches(line).next();
+        self.detected.set(detected);
+        first.map(|m| m.time_ns)
+    }
+
+    /// Returns the timestamps detected in `line` without counting them as detected.
+    fn peek_matches(&self, line: &str) -> Vec<Match> {
+        let detected = self.detected.get();
+        let matches = self.matches(line).collect();
+        self.detected.set(detected);
+        matches
+    }
+
+    /// Returns an iterator over the timestamps detected in `line`.
+    fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Match> + 'a {
+        self.detector
+            .matches_with(line, move |candidate| self.parse_candidate(line, candidate))
+            .filter(|_| {
+                let detected = self.detected.get() + 1;
+                self.detected.set(detected);
+                self.max_per_line.is_none_or(|max| detected <= max)
+                    && self.occurrence.is_none_or(|n| detected == n)
+            })
+    }
+
+    /// Parses `candidate` in `line` if it's a timestamp in a selected key or column.
+    fn parse_candidate(&self, line: &str, candidate: &Candidate) -> Option<(i64, Precision)> {
+        let start = candidate.range.start;
+        if !self.kv_keys.is_empty()
+            && !kv_key(line, start)
+                .is_some_and(|key| self.kv_keys.iter().any(|pattern| glob_match(pattern, key)))
+        {
+            return None;
+        }
+        let number = &line[candidate.range.clone()];
+        if !self.columns.is_empty() {
+            let field = csv_field(line, start);
+            let (_, detector) = self.columns.iter().find(|(column, _)| *column == field)?;
+            // Fields without a unit are detected like elsewhere
+            if let Some(detector) = detector {
+                return self.parse_in_unit(detector, number);
+            }
+        }
+        if candidate.is_fraction {
+            // Fractional seconds are only detected at the start of a line, like the
+            // timestamps of `strace -ttt`, since elsewhere they're usually durations
+            let is_line_start = line[..start].trim_start().is_empty();
+            is_line_start
+                .then(|| self.parse_fractional_seconds(number))
+                .flatten()
+                .or_else(|| self.parse_fraction(number))
+        } else if self.fractions_only {
+            None
+        } else {
+            self.parse(number)
+        }
+    }
+
+    fn parse(&self, number: &str) -> Option<(i64, Precision)> {
+        // If the number has too few or too many digits to be in any bound, can skip parsing
+        if !(self.min_len..=self.max_len).contains(&number.trim_start_matches('-').len()) {
+            return None;
+        }
+        let n: i64 = number.parse().ok()?;
+        if let Some((time_ns, precision)) = self.detector.parse(n) {
+            return Some((self.to_utc(time_ns), precision));
+        }
+        let days = n as f64;
+        let (day_count, _) = self
+            .day_bounds
+            .iter()
+            .find(|(day_count, b)| day_count.detects_integers() && b.contains(&days))?;
+        Some((day_count.to_nanos(days)?, Precision::Days))
+    }
+
+    /// Parses an integer timestamp in the unit of `detector`, like a field of `--columns`.
+    fn parse_in_unit(&self, detector: &Detector, number: &str) -> Option<(i64, Precision)> {
+        let (time_ns, precision) = detector.parse(number.parse().ok()?)?;
+        Some((self.to_utc(time_ns), precision))
+    }
+
+    /// Parses seconds with a fractional part of up to nanosecond precision like
+    /// `1709152989.456123`.
+    fn parse_fractional_seconds(&self, number: &str) -> Option<(i64, Precision)> {
+        let (seconds, fraction) = number.split_once('.')?;
+        if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let seconds: i64 = seconds.parse().ok()?;
+        let (time_ns, precision) = self.detector.parse(seconds)?;
+        // Hours and minutes also have a precision of seconds
+        let unit = self.detector.units(seconds.checked_abs()?).next();
+        if precision != Precision::Seconds || unit.is_some_and(|unit| unit != Unit::Seconds) {
+            return None;
+        }
+        let fraction_ns: i64 = format!("{fraction:0<9}").parse().ok()?;
+        let precision = match fraction.len() {
+            1..=3 => Precision::Millis,
+            4..=6 => Precision::Micros,
+            _ => Precision::Nanos,
+        };
+        Some((self.to_utc(time_ns.checked_add(fraction_ns)?), precision))
+    }
+
+    /// Converts an epoch timestamp in `--scale` to UTC.
+    fn to_utc(&self, time_ns: i64) -> i64 {
+        match &self.time_scale {
+            Some(time_scale) => time_scale.to_utc(time_ns),
+            None => time_ns,
+        }
+    }
+
+    fn parse_fraction(&self, number: &str) -> Option<(i64, Precision)> {
+        if self.day_bounds.is_empty() {
+            return None;
         }
+        let days = number.parse().ok()?;
+        let (day_count, _) = self.day_bounds.iter().find(|(_, b)| b.contains(&days))?;
+        Some((day_count.to_nanos(days)?, Precision::Seconds))
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+/// Parses a timestamp rendered with `precision` back to nanoseconds since the UNIX
+/// epoch, ignoring any annotations.
+fn parse_rendered(time: &str, precision: Precision) -> Option<i64> {
+    let time = time.split_once(" (").map_or(time, |(time, _)| time);
+    if precision == Precision::Days {
+        let date = NaiveDate::parse_from_str(time, "%Y-%m-%d").ok()?;
+        return date
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+            .timestamp_nanos_opt();
+    }
+    DateTime::parse_from_rfc3339(time)
+        .ok()?
+        .timestamp_nanos_opt()
+}
 
-    let reformatter = Reformatter::new(args.threshold, args.local, args.quote);
-    let mut output: Box<dyn Write> = if let Some(path) = args.output {
-        Box::new(BufWriter::new(File::create(path)?))
+/// Opens the input file or URL, or STDIN if there isn't one.
+fn open_input(args: &Args) -> anyhow::Result<Box<dyn BufRead>> {
+    let mut input = open_uncounted_input(args)?;
+    // Binary formats have their own encodings of strings
+    if args.input_format == InputFormat::Text {
+        input = encoding::decode(input, args.encoding);
+    }
+    Ok(if args.timing {
+        Box::new(timing::Counted(input))
     } else {
-        Box::new(BufWriter::new(stdout().lock()))
+        input
+    })
+}
+
+fn open_uncounted_input(args: &Args) -> anyhow::Result<Box<dyn BufRead>> {
+    #[cfg(feature = "http")]
+    if let Some(url) = args.input.as_deref().and_then(|input| input.to_str()) {
+        if http::is_url(url) {
+            if args.follow {
+                bail!("only files can be followed, not `{url}`");
+            }
+            return http::open(url, &args.header);
+        }
+    }
+    let Some(input_file) = &args.input else {
+        return Ok(Box::new(stdin().lock()));
     };
+    if let Some(socket) = unix_socket(input_file) {
+        if args.follow {
+            bail!("only files can be followed, not `{}`", input_file.display());
+        }
+        return Ok(Box::new(BufReader::new(connect_unix_socket(socket)?)));
+    }
+    if args.follow {
+        let file = follow::Follow::open(input_file.clone())?;
+        return Ok(Box::new(BufReader::new(file)));
+    }
+    let file = File::open(input_file)?;
+    #[cfg(feature = "progress")]
+    if progress::is_enabled(args, &file)? {
+        return progress::wrap(file);
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Returns the path of a Unix domain socket given as `unix:PATH`.
+fn unix_socket(path: &Path) -> Option<&Path> {
+    path.to_str()?.strip_prefix("unix:").map(Path::new)
+}
+
+#[cfg(unix)]
+fn connect_unix_socket(path: &Path) -> anyhow::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(path)
+        .with_context(|| format!("failed to connect to {}", path.display()))
+}
 
-    if let Some(input_file) = args.input {
-        for line in BufReader::new(File::open(input_file)?).lines() {
-            reformatter.write(&mut output, &line?)?;
-            output.write_all(b"\n")?;
+#[cfg(not(unix))]
+fn connect_unix_socket(path: &Path) -> anyhow::Result<File> {
+    bail!(
+        "failed to connect to {}: Unix domain sockets aren't supported on this platform",
+        path.display()
+    )
+}
+
+fn convert_text(
+    args: &Args,
+    reformatter: &Reformatter,
+    mut output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let unscrubber = args
+        .unscrub
+        .as_deref()
+        .map(scrub::Unscrubber::load)
+        .transpose()?;
+    // The time and precision of the last timestamp, for `--gaps`
+    let last_time = Cell::new(None);
+    // Reports gaps between the timestamps in `line` and the ones before it
+    let check_gaps = |output: &mut dyn Write, line_no: usize, line: &str| {
+        let Some(max_gap) = args.gaps else {
+            return Ok(());
+        };
+        for m in reformatter.peek_matches(line) {
+            let Some((last_ns, last_precision)) = last_time.replace(Some((m.time_ns, m.precision)))
+            else {
+                continue;
+            };
+            let gap = m.time_ns.saturating_sub(last_ns);
+            if gap <= max_gap {
+                continue;
+            }
+            let gap = duration::format(gap);
+            if args.gaps_to_stderr || args.output_format == OutputFormat::Jsonl {
+                let last = epoch_to::format_utc(last_ns, last_precision);
+                eprintln!("line {line_no}: gap of {gap} after {last}");
+            } else {
+                let ending = if args.null { "\0" } else { "\n" };
+                write!(output, "-- gap of {gap} --{ending}")?;
+            }
+        }
+        anyhow::Ok(())
+    };
+    // Writes `line` followed by `ending`, except in modes with their own line structure.
+    // The caller starts the line, since it may be converted in pieces
+    let write_converted = |mut output: &mut dyn Write, line_no: usize, line: &str, ending: &str| {
+        // The rest of a long line passed through in pieces continues the line
+        if args.line_numbers && reformatter.line_offset.get() == 0 {
+            write!(output, "{line_no}:")?;
+        }
+        if let Some(unscrubber) = &unscrubber {
+            write!(output, "{}{ending}", unscrubber.restore(line))?;
+            Ok(())
+        } else if args.extract {
+            reformatter.write_extracted(&mut output, line_no, line, args.with_offset)
+        } else if args.output_format == OutputFormat::Jsonl {
+            reformatter.write_jsonl(&mut output, line_no, line)
+        } else if args.tsv {
+            reformatter.write_tsv(&mut output, line)
+        } else if args.verify_passthrough {
+            let replacements = reformatter.replacements(line)?;
+            let mut converted = Vec::new();
+            reformatter.write_replaced(&mut converted, line, &replacements)?;
+            // Only timestamps are replaced, so the text remains valid UTF-8
+            let converted = String::from_utf8(converted).expect("valid UTF-8");
+            reformatter
+                .verify_passthrough(line, &replacements, &converted)
+                .with_context(|| format!("pass-through verification failed on line {line_no}"))?;
+            write!(output, "{converted}{ending}")?;
+            Ok(())
+        } else {
+            reformatter.write(&mut output, line)?;
+            output.write_all(ending.as_bytes())?;
+            Ok(())
+        }
+    };
+    // Writes `line` like `write_converted` unless it's dropped for having timestamps or not
+    let convert_line = |output: &mut dyn Write, line_no: usize, line: &str, ending: &str| {
+        check_gaps(output, line_no, line)?;
+        if !args.only_matches && !args.invert {
+            return write_converted(output, line_no, line, ending);
         }
-    } else if let Some((last, rest)) = args.strings.split_last() {
-        for arg in rest {
-            reformatter.write(&mut output, arg)?;
-            output.write_all(b" ")?;
+        let conversions = reformatter.conversions.get();
+        let mut converted = Vec::new();
+        write_converted(&mut converted, line_no, line, ending)?;
+        let has_timestamps = reformatter.conversions.get() > conversions;
+        if has_timestamps != args.invert {
+            output.write_all(&converted)?;
         }
-        reformatter.write(&mut output, last)?;
-        output.write_all(b"\n")?;
+        anyhow::Ok(())
+    };
+    // Only the plain conversion passes invalid UTF-8 through unchanged. Other modes
+    // replace it, since their output is derived from the text
+    let is_plain = unscrubber.is_none()
+        && !args.only_matches
+        && !args.invert
+        && !args.extract
+        && !args.tsv
+        && !args.verify_passthrough
+        && args.output_format == OutputFormat::Text;
+    let filter = (args.since.is_some() || args.until.is_some()).then(|| {
+        let now = Utc::now();
+        let zone = Reformatter::zone(args);
+        let to_nanos = |time: TimeArg| time.resolve(zone, now).saturating_mul(1_000_000_000);
+        let context = args.context.unwrap_or_default();
+        RefCell::new(filter::TimeFilter::new(
+            args.since.map(to_nanos),
+            args.until.map(to_nanos),
+            args.before_context.unwrap_or(context),
+            args.after_context.unwrap_or(context),
+        ))
+    });
+    // Separate groups of lines like `grep`, ending the separator like a record
+    let group_separator: &[u8] = if args.null { b"--\0" } else { b"--\n" };
+    // Converts `line` if it's kept by the filter, along with any context before it
+    let write_line = |output: &mut dyn Write, line_no: usize, line: &str, ending: &'static str| {
+        let Some(filter) = &filter else {
+            return convert_line(output, line_no, line, ending);
+        };
+        let line = filter::Line {
+            no: line_no,
+            text: line.to_owned(),
+            ending,
+        };
+        let time_ns = reformatter.first_time(&line.text);
+        filter
+            .borrow_mut()
+            .push(line, time_ns, |line, is_separated| {
+                if is_separated && is_plain {
+                    output.write_all(group_separator)?;
+                }
+                reformatter.start_line(line.no);
+                convert_line(output, line.no, &line.text, line.ending)
+            })
+    };
+
+    // Flushing each line is only worth the syscalls when someone's watching, which
+    // following implies
+    let line_buffered = args.line_buffered
+        || (!args.block_buffered
+            && (args.follow || (args.output.is_none() || args.tee) && stdout().is_terminal()));
+    #[cfg(feature = "clipboard")]
+    if args.clipboard {
+        for (i, line) in clipboard::paste()?.lines().enumerate() {
+            reformatter.start_line(i + 1);
+            write_line(output, i + 1, line, "\n")?;
+        }
+        return Ok(());
+    }
+    let (separator, ending) = if args.null {
+        (b'\0', "\0")
     } else {
-        for line in stdin().lock().lines() {
-            reformatter.write(&mut output, &line?)?;
-            output.write_all(b"\n")?;
+        (b'\n', "\n")
+    };
+    if args.input.is_none() && !args.strings.is_empty() {
+        reformatter.start_line(1);
+        return write_line(output, 1, &args.strings.join(" "), ending);
+    }
+    if args.no_line_mode {
+        return convert_chunks(reformatter, open_input(args)?, output, line_buffered);
+    }
+    let mut input = open_input(args)?;
+    let mut buf = Vec::new();
+    let mut line_no = 0;
+    // The offset in the current line of its rest in `buf`, after streaming pieces of it
+    let mut offset = 0;
+    loop {
+        let is_eof = match args.max_line_bytes {
+            // Read at most the rest of the limit, carrying over what's left of a long line
+            Some(max) => {
+                let limit = max.saturating_sub(buf.len() as u64);
+                input.by_ref().take(limit).read_until(separator, &mut buf)? == 0
+            }
+            None => input.read_until(separator, &mut buf)? == 0,
+        };
+        if buf.is_empty() {
+            break;
+        }
+        if !is_eof
+            && !buf.ends_with(&[separator])
+            && args
+                .max_line_bytes
+                .is_some_and(|max| buf.len() as u64 >= max)
+        {
+            // Pass the long line through a piece at a time, keeping its line number
+            let split = split_point(&buf).unwrap_or(buf.len());
+            let piece = &buf[..split];
+            if offset == 0 {
+                reformatter.start_line(line_no + 1);
+            } else {
+                reformatter.continue_line(offset);
+            }
+            if is_plain {
+                check_gaps(output, line_no + 1, &String::from_utf8_lossy(piece))?;
+                if args.line_numbers && offset == 0 {
+                    write!(output, "{}:", line_no + 1)?;
+                }
+                reformatter.write_bytes(&mut output, piece)?;
+            } else {
+                write_line(output, line_no + 1, &String::from_utf8_lossy(piece), "")?;
+            }
+            offset += split;
+            buf.drain(..split);
+            continue;
+        }
+        line_no += 1;
+        if offset == 0 {
+            reformatter.start_line(line_no);
+        } else {
+            reformatter.continue_line(offset);
+            offset = 0;
+        }
+        // Reproduce each line's ending, including a missing one at the end of the input
+        let (line, ending) = if let Some(line) = buf.strip_suffix(&[separator]) {
+            match line.strip_suffix(b"\r") {
+                Some(line) if !args.null => (line, "\r\n"),
+                _ => (line, ending),
+            }
+        } else {
+            (buf.as_slice(), "")
+        };
+        match std::str::from_utf8(line) {
+            Ok(line) => write_line(output, line_no, line, ending)?,
+            Err(_) if is_plain && filter.is_none() => {
+                check_gaps(output, line_no, &String::from_utf8_lossy(line))?;
+                if args.line_numbers && reformatter.line_offset.get() == 0 {
+                    write!(output, "{line_no}:")?;
+                }
+                reformatter.write_bytes(&mut output, line)?;
+                output.write_all(ending.as_bytes())?;
+            }
+            Err(_) => write_line(output, line_no, &String::from_utf8_lossy(line), ending)?,
+        }
+        if line_buffered {
             output.flush()?;
         }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Converts `input` in chunks of about `CHUNK_SIZE` bytes, split after a delimiter so
+/// timestamps and other tokens aren't split between chunks.
+fn convert_chunks(
+    reformatter: &Reformatter,
+    mut input: Box<dyn BufRead>,
+    mut output: &mut dyn Write,
+    flush: bool,
+) -> anyhow::Result<()> {
+    const CHUNK_SIZE: usize = 64 * 1024;
+    // Give up on finding a delimiter after this many bytes, passing the rest of a
+    // split character through as is
+    const MAX_CHUNK_SIZE: usize = 16 * CHUNK_SIZE;
+
+    let mut buf = Vec::with_capacity(2 * CHUNK_SIZE);
+    let mut line_no = 1;
+    // The offset in the current line of the start of the next chunk
+    let mut offset = 0;
+    loop {
+        let is_eof = input
+            .by_ref()
+            .take(CHUNK_SIZE as u64)
+            .read_to_end(&mut buf)?
+            == 0;
+        let split = if is_eof {
+            Some(buf.len())
+        } else {
+            split_point(&buf).or((buf.len() >= MAX_CHUNK_SIZE).then_some(buf.len()))
+        };
+        let Some(split) = split else {
+            continue;
+        };
+        // Convert each line of the chunk separately so they're numbered like in line mode
+        for piece in buf[..split].split_inclusive(|&b| b == b'\n') {
+            if offset == 0 {
+                reformatter.start_line(line_no);
+            } else {
+                reformatter.continue_line(offset);
+            }
+            reformatter.write_bytes(&mut output, piece)?;
+            if piece.ends_with(b"\n") {
+                line_no += 1;
+                offset = 0;
+            } else {
+                offset += piece.len();
+            }
+        }
+        if flush {
+            output.flush()?;
+        }
+        buf.drain(..split);
+        if is_eof {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns the index just past the last delimiter in `buf`, preferring the end of a
+/// line, which keeps multi-word dates like syslog's together. Delimiters within a
+/// `key=value` pair or JSON `"key": value` member don't count, so `--kv` still finds the
+/// key of a value at the start of the next piece.
+fn split_point(buf: &[u8]) -> Option<usize> {
+    if let Some(i) = buf.iter().rposition(|&b| b == b'\n') {
+        return Some(i + 1);
+    }
+    (0..buf.len())
+        .rev()
+        .filter(|&i| buf[i].is_ascii_whitespace() || b",;()[]{}<>|".contains(&buf[i]))
+        .find(|&i| {
+            let before = buf[..=i].trim_ascii_end();
+            !before.ends_with(b":") && !before.ends_with(b"=")
+        })
+        .map(|i| i + 1)
+}
+
+/// Writes the man page of `epoch` to STDOUT, or the pages of `epoch` and each
+/// subcommand to `dir`.
+fn write_man(dir: Option<&Path>) -> anyhow::Result<()> {
+    // Name the pages after the binary rather than the package
+    let command = Args::command().name("epoch");
+    let Some(dir) = dir else {
+        clap_mangen::Man::new(command).render(&mut stdout().lock())?;
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    clap_mangen::generate_to(command, dir)
+        .with_context(|| format!("failed to write man pages to {}", dir.display()))
+}
+
+/// Converts the input in its format.
+fn convert(
+    args: &Args,
+    reformatter: &Reformatter,
+    mut output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let converter = binary::Converter::new(reformatter, &args.keys);
+    match args.input_format {
+        InputFormat::Text => convert_text(args, reformatter, output),
+        InputFormat::Msgpack => converter.convert_msgpack(open_input(args)?, &mut output),
+        InputFormat::Cbor => converter.convert_cbor(open_input(args)?, &mut output),
+        #[cfg(feature = "dbn")]
+        InputFormat::Dbn => converter.convert_dbn(open_input(args)?, &mut output),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse_from(preset::expand(
+        std::env::args_os().collect(),
+        &Args::command(),
+    )?);
+    let start = Instant::now();
+
+    match &args.command {
+        Some(Command::Explain { number }) => {
+            return explain::explain(&Reformatter::new(&args)?, number);
+        }
+        Some(Command::Add {
+            timestamp,
+            duration,
+        }) => {
+            return arithmetic::shift(&Reformatter::new(&args)?, timestamp, *duration);
+        }
+        Some(Command::Sub {
+            timestamp,
+            duration,
+        }) => {
+            let duration = duration.checked_neg().context("duration is too long")?;
+            return arithmetic::shift(&Reformatter::new(&args)?, timestamp, duration);
+        }
+        #[cfg(unix)]
+        Some(Command::Docker { container, follow }) => {
+            return docker::docker(&args, container, *follow);
+        }
+        Some(Command::Hist {
+            bucket,
+            output_format,
+            width,
+        }) => return hist::hist(&args, *bucket, *output_format, *width),
+        Some(Command::Journal { unit, follow }) => return journal::journal(&args, unit, *follow),
+        #[cfg(feature = "kafka")]
+        Some(Command::Kafka {
+            brokers,
+            topic,
+            from_beginning,
+            message_time,
+        }) => return kafka::kafka(&args, brokers, topic, *from_beginning, *message_time),
+        Some(Command::Man { dir }) => return write_man(dir.as_deref()),
+        #[cfg(feature = "parquet")]
+        Some(Command::Parquet { file, columns }) => {
+            return columnar::print_parquet(&Reformatter::new(&args)?, file, columns);
+        }
+        Some(Command::Serve { listen }) => return serve::serve(&args, *listen),
+        #[cfg(feature = "self-update")]
+        Some(Command::SelfUpdate { check }) => return update::self_update(*check),
+        #[cfg(feature = "tui")]
+        Some(Command::View { file }) => {
+            let mut reformatter = Reformatter::new(&Args {
+                input: Some(file.clone()),
+                ..args.clone()
+            })?;
+            return view::view(file, &mut reformatter);
+        }
+        None => {}
     }
 
+    let reformatter = Reformatter::new(&args)?;
+    #[cfg(feature = "clipboard")]
+    let has_input = args.input.is_some() || !args.strings.is_empty() || args.clipboard;
+    #[cfg(not(feature = "clipboard"))]
+    let has_input = args.input.is_some() || !args.strings.is_empty();
+    if args.interactive || (!has_input && stdin().is_terminal()) {
+        return repl::run(&reformatter);
+    }
+    let mut output = output::open(&args)?;
+
+    #[cfg(feature = "clipboard")]
+    if args.copy {
+        let mut copied = Vec::new();
+        convert(&args, &reformatter, &mut copied)?;
+        output.write_all(&copied)?;
+        clipboard::copy(&String::from_utf8_lossy(&copied))?;
+    } else {
+        convert(&args, &reformatter, &mut output)?;
+    }
+    #[cfg(not(feature = "clipboard"))]
+    convert(&args, &reformatter, &mut output)?;
+
+    if let Some((scrubber, path)) = reformatter.scrubber.as_ref().zip(args.map_file.as_deref()) {
+        scrubber.write_map(path)?;
+    }
+    output.finish()?;
+    if args.timing {
+        timing::report(start, reformatter.conversions.get());
+    }
+    if args.fail_if_none && reformatter.conversions.get() == 0 {
+        std::process::exit(1);
+    }
     Ok(())
 }
@@ -0,0 +1,166 @@
+//! Destinations of the converted output.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, stdout, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::Context;
+
+use crate::{connect_unix_socket, unix_socket, Args};
+
+/// The destination of the output. When writing a file atomically, the output goes to a
+/// temporary file that only replaces the destination once finished, and is removed
+/// otherwise, including when interrupted by `SIGINT`, `SIGTERM`, or `SIGHUP` on Unix.
+/// Other signals like `SIGKILL`, or Ctrl-C on Windows, leave it behind.
+pub struct Output {
+    writer: Box<dyn Write>,
+    /// The temporary file and the destination to rename it to when finished.
+    rename: Option<(PathBuf, PathBuf)>,
+}
+
+/// Opens the destination of the output selected by `args`.
+pub fn open(args: &Args) -> anyhow::Result<Output> {
+    let Some(path) = &args.output else {
+        return Ok(Output {
+            writer: Box::new(BufWriter::new(stdout().lock())),
+            rename: None,
+        });
+    };
+    if let Some(socket) = unix_socket(path) {
+        return Ok(Output {
+            writer: tee(args, BufWriter::new(connect_unix_socket(socket)?)),
+            rename: None,
+        });
+    }
+    // Replacing anything but a regular file, like a FIFO or `/dev/null`, would break it,
+    // and a followed input never finishes
+    let is_atomic = !args.no_atomic
+        && !args.append
+        && !args.follow
+        && fs::metadata(path).map_or(true, |metadata| metadata.is_file());
+    let (file, rename) = if is_atomic {
+        // Replace the target of a symlink rather than the link itself
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        let temp = temp_path(&path)?;
+        #[cfg(unix)]
+        remove_on_signal(temp.clone())?;
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("failed to create {}", temp.display()))?;
+        // Keep the permissions of the file being replaced
+        if let Ok(metadata) = fs::metadata(&path) {
+            fs::set_permissions(&temp, metadata.permissions())
+                .with_context(|| format!("failed to set the permissions of {}", temp.display()))?;
+        }
+        (file, Some((temp, path)))
+    } else {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(args.append)
+            .truncate(!args.append)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        (file, None)
+    };
+    Ok(Output {
+        writer: tee(args, BufWriter::new(file)),
+        rename,
+    })
+}
+
+/// Returns `writer`, also writing to STDOUT with `--tee`.
+fn tee(args: &Args, writer: impl Write + 'static) -> Box<dyn Write> {
+    if args.tee {
+        Box::new(Tee(writer, BufWriter::new(stdout().lock())))
+    } else {
+        Box::new(writer)
+    }
+}
+
+/// Returns a hidden path next to `path` to write to before renaming, since renames are
+/// only atomic within a file system.
+fn temp_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .with_context(|| format!("expected a file path, found {}", path.display()))?;
+    let mut temp = OsString::from(".");
+    temp.push(name);
+    temp.push(format!(".{}.tmp", process::id()));
+    Ok(path.with_file_name(temp))
+}
+
+/// Removes `temp` and exits like the default handler would if the process is
+/// interrupted, from a thread rather than the signal handler since that can't allocate.
+#[cfg(unix)]
+fn remove_on_signal(temp: PathBuf) -> anyhow::Result<()> {
+    use signal_hook::{
+        consts::{SIGHUP, SIGINT, SIGTERM},
+        iterator::Signals,
+    };
+
+    let mut signals =
+        Signals::new([SIGHUP, SIGINT, SIGTERM]).context("failed to handle signals")?;
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            let _ = fs::remove_file(&temp);
+            process::exit(128 + signal);
+        }
+    });
+    Ok(())
+}
+
+impl Output {
+    /// Flushes the output and moves it into place.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        if let Some((temp, path)) = self.rename.take() {
+            // Close the file first, which Windows requires to rename it
+            self.writer = Box::new(io::sink());
+            fs::rename(&temp, &path)
+                .with_context(|| format!("failed to replace {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if let Some((temp, _)) = self.rename.take() {
+            self.writer = Box::new(io::sink());
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes everything to both writers, like `tee`.
+struct Tee<A, B>(A, B);
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
@@ -0,0 +1,120 @@
+//! Custom detectors loaded from WebAssembly modules for `--plugin`.
+//!
+//! A plugin exports its `memory` and two functions:
+//!
+//! - `alloc(len: i32) -> i32` returns the address of `len` bytes for the host to copy a
+//!   line of UTF-8 into. It's called before each `detect`, so a plugin can reset a bump
+//!   allocator in it.
+//! - `detect(ptr: i32, len: i32) -> i32` finds the timestamps in the line at `ptr` and
+//!   returns the address of a little-endian `u32` count of matches followed by that many
+//!   20-byte matches: the `u32` start and end byte offsets of the match in the line, an
+//!   `i64` of nanoseconds since the UNIX epoch, and a `u32` precision to render it with:
+//!   0 for dates, then 1 to 4 for seconds, milliseconds, microseconds, or nanoseconds.
+//!
+//! Matches are replaced with their formatted times.
+
+use std::{cell::RefCell, fmt, fs, ops::Range, path::Path};
+
+use anyhow::{bail, Context};
+use chrono::DateTime;
+use epoch_to::Precision;
+use wasmi::{Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::ids;
+
+/// The size of each match returned by `detect`.
+const MATCH_SIZE: usize = 20;
+
+pub struct Plugin {
+    name: String,
+    store: RefCell<Store<()>>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    detect: TypedFunc<(i32, i32), i32>,
+}
+
+impl Plugin {
+    /// Loads and instantiates the WebAssembly module at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let name = path.display().to_string();
+        let wasm = fs::read(path).with_context(|| format!("failed to read {name}"))?;
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm)
+            .with_context(|| format!("failed to load the plugin {name}"))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .with_context(|| format!("failed to instantiate the plugin {name}"))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .with_context(|| format!("the plugin {name} doesn't export `memory`"))?;
+        let alloc = instance
+            .get_typed_func(&store, "alloc")
+            .with_context(|| format!("the plugin {name} doesn't export `alloc(i32) -> i32`"))?;
+        let detect = instance.get_typed_func(&store, "detect").with_context(|| {
+            format!("the plugin {name} doesn't export `detect(i32, i32) -> i32`")
+        })?;
+        Ok(Self {
+            name,
+            store: RefCell::new(store),
+            memory,
+            alloc,
+            detect,
+        })
+    }
+
+    /// Returns the timestamps the plugin detects in `line`.
+    pub fn detect(&self, line: &str) -> anyhow::Result<Vec<ids::Id>> {
+        self.call(line)
+            .with_context(|| format!("the plugin {} failed", self.name))
+    }
+
+    fn call(&self, line: &str) -> anyhow::Result<Vec<ids::Id>> {
+        let mut store = self.store.borrow_mut();
+        let len = i32::try_from(line.len()).context("the line is too long")?;
+        let ptr = self.alloc.call(&mut *store, len)?;
+        self.memory
+            .write(&mut *store, ptr as u32 as usize, line.as_bytes())?;
+        let results = self.detect.call(&mut *store, (ptr, len))? as u32 as usize;
+
+        let memory = self.memory.data(&*store);
+        let bytes = |range: Range<usize>| {
+            memory
+                .get(range.clone())
+                .with_context(|| format!("bytes {range:?} are out of bounds"))
+        };
+        let u32_at = |at: usize| {
+            bytes(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+        };
+        let count = u32_at(results)?;
+        let mut matches = Vec::with_capacity(count);
+        for i in 0..count {
+            let at = results + 4 + i * MATCH_SIZE;
+            let range = u32_at(at)?..u32_at(at + 4)?;
+            let time_ns = i64::from_le_bytes(bytes(at + 8..at + 16)?.try_into().unwrap());
+            let precision = match u32_at(at + 16)? {
+                0 => Precision::Days,
+                1 => Precision::Seconds,
+                2 => Precision::Millis,
+                3 => Precision::Micros,
+                4 => Precision::Nanos,
+                precision => bail!("expected a precision from 0 to 4, found {precision}"),
+            };
+            if range.start >= range.end
+                || !line.is_char_boundary(range.start)
+                || !line.is_char_boundary(range.end)
+                || range.end > line.len()
+            {
+                bail!("expected a match within the line, found bytes {range:?}");
+            }
+            matches.push((range, DateTime::from_timestamp_nanos(time_ns), precision));
+        }
+        Ok(matches)
+    }
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin").field("name", &self.name).finish()
+    }
+}
@@ -0,0 +1,236 @@
+//! Named bundles of options for known log formats, along with custom ones from the config
+//! file.
+//!
+//! The config file is `$EPOCH_CONFIG`, or `epoch/config.toml` in `$XDG_CONFIG_HOME`,
+//! `~/.config`, or `%APPDATA%` on Windows. Presets are arrays of arguments:
+//!
+//! ```toml
+//! [presets]
+//! orders = ["--unit", "ms", "--kv", "--keys", "ts,sent"]
+//! ```
+
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context};
+
+/// Presets for common log formats.
+const BUILT_IN: [(&str, &[&str]); 5] = [
+    // Nanosecond event times in CME gateway logs, which run on Chicago time
+    (
+        "cme-gateway",
+        &["--unit", "ns", "--word-boundary", "--tz", "America/Chicago"],
+    ),
+    // The nanosecond time fields of DBN records encoded as JSON, but not their prices or
+    // order IDs
+    (
+        "databento",
+        &[
+            "--unit",
+            "ns",
+            "--kv",
+            "--keys",
+            "ts_event,ts_recv,ts_ref,expiration,activation",
+        ],
+    ),
+    // Container log prefixes and structured `ts` fields
+    (
+        "kubernetes",
+        &["--reformat-iso", "--kv", "--keys", "ts,time,timestamp"],
+    ),
+    // Access log dates and epochs in custom fields, but not digits within words like
+    // request paths
+    ("nginx", &["--access-log", "--word-boundary"]),
+    // The times of `strace -ttt` at the start of each line, but no whole numbers like
+    // those in system call arguments. The times of day of `-t` and `-tt` are left as is
+    ("strace", &["--fractions-only"]),
+];
+
+/// Returns `args` with the options of each `--preset` of `command` added, except those
+/// given explicitly or by a later preset, which take precedence.
+pub fn expand(args: Vec<OsString>, command: &clap::Command) -> anyhow::Result<Vec<OsString>> {
+    let names = preset_names(&args);
+    if names.is_empty() {
+        return Ok(args);
+    }
+    let custom = load_custom()?;
+    let mut given: Vec<String> = args
+        .iter()
+        .skip(1)
+        .map_while(|arg| arg.to_str().filter(|&arg| arg != "--"))
+        .filter_map(|arg| long_name(arg, command))
+        .collect();
+    let mut expanded = Vec::new();
+    for name in names.into_iter().rev() {
+        let options = match custom.iter().find(|(custom_name, _)| *custom_name == name) {
+            Some((_, options)) => options.clone(),
+            None => match BUILT_IN.iter().find(|(built_in, _)| *built_in == name) {
+                Some((_, options)) => options.iter().map(|&option| option.to_owned()).collect(),
+                None => bail!(
+                    "unknown preset `{name}`, expected one of {}, or a preset in {}",
+                    BUILT_IN.map(|(name, _)| name).join(", "),
+                    config_path().map_or("the config file".to_owned(), |path| path
+                        .display()
+                        .to_string()),
+                ),
+            },
+        };
+        // Split the options into each flag and its values
+        let mut rest = &options[..];
+        let mut added = Vec::new();
+        while let Some((flag, after)) = rest.split_first() {
+            let (Some(long), Some(arg)) = (long_name(flag, command), find_arg(flag, command))
+            else {
+                bail!("unknown option `{flag}` in preset `{name}`");
+            };
+            let end = 1 + value_count(flag, arg, after);
+            let (option, after) = rest.split_at(end);
+            rest = after;
+            if !given.contains(&long) {
+                match option {
+                    // Attach single values so clap accepts those starting with `-`
+                    [_, value] => expanded.push(format!("--{long}={value}").into()),
+                    _ => expanded.extend(option.iter().map(OsString::from)),
+                }
+                added.push(long);
+            }
+        }
+        given.extend(added);
+    }
+    let mut args = args.into_iter();
+    Ok(args
+        .next()
+        .into_iter()
+        .chain(expanded)
+        .chain(args)
+        .collect())
+}
+
+/// Returns the long name of the option `arg` of `command`, like `unit` for `-u`,
+/// `--unit`, or `--unit=s`.
+fn long_name(arg: &str, command: &clap::Command) -> Option<String> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let long = long.split_once('=').map_or(long, |(long, _)| long);
+        return Some(long.to_owned());
+    }
+    let short = arg.strip_prefix('-')?.chars().next()?;
+    command
+        .get_arguments()
+        .find(|option| option.get_short() == Some(short))
+        .and_then(|option| option.get_long())
+        .map(str::to_owned)
+}
+
+/// Returns the option `arg` of `command`, like `--unit`, `--unit=s`, `-u`, or `-us`.
+fn find_arg<'a>(arg: &str, command: &'a clap::Command) -> Option<&'a clap::Arg> {
+    let long = long_name(arg, command)?;
+    command
+        .get_arguments()
+        .find(|option| option.get_long() == Some(long.as_str()))
+}
+
+/// Returns how many of the arguments `after` the option `flag` are its values, which may
+/// start with `-` like `-5h` when the option requires a value.
+fn value_count(flag: &str, arg: &clap::Arg, after: &[String]) -> usize {
+    // Values given with `=` or attached to a short flag like `-us`
+    let is_attached = flag.contains('=') || (!flag.starts_with("--") && flag.len() > 2);
+    if is_attached || arg.is_require_equals_set() {
+        return 0;
+    }
+    let num_args = arg.get_num_args().unwrap_or_else(|| {
+        if arg.get_action().takes_values() {
+            1.into()
+        } else {
+            0.into()
+        }
+    });
+    let mut count = 0;
+    while count < after.len()
+        && count < num_args.max_values()
+        && (count < num_args.min_values() || !after[count].starts_with('-'))
+    {
+        count += 1;
+    }
+    count
+}
+
+/// Returns the values of the `--preset` options in `args`, including `databento` for
+/// `--databento`.
+fn preset_names(args: &[OsString]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut args = args.iter().skip(1).map(OsString::as_os_str);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        let name = if arg == "--preset" {
+            args.next()
+        } else if arg == "--databento" {
+            Some(OsStr::new("databento"))
+        } else {
+            arg.to_str()
+                .and_then(|arg| arg.strip_prefix("--preset="))
+                .map(OsStr::new)
+        };
+        if let Some(name) = name {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+    names
+}
+
+/// Loads the presets defined in the config file, if there is one.
+fn load_custom() -> anyhow::Result<Vec<(String, Vec<String>)>> {
+    let Some(path) = config_path() else {
+        return Ok(Vec::new());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let config: toml::Table = text
+        .parse()
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    let Some(presets) = config.get("presets") else {
+        return Ok(Vec::new());
+    };
+    let presets = presets
+        .as_table()
+        .with_context(|| format!("expected a `presets` table in {}", path.display()))?;
+    presets
+        .iter()
+        .map(|(name, options)| {
+            let options = options
+                .as_array()
+                .and_then(|options| {
+                    options
+                        .iter()
+                        .map(|option| option.as_str().map(str::to_owned))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .with_context(|| {
+                    format!(
+                        "expected preset `{name}` in {} to be an array of strings",
+                        path.display()
+                    )
+                })?;
+            Ok((name.clone(), options))
+        })
+        .collect()
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("EPOCH_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("epoch").join("config.toml"))
+}
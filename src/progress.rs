@@ -0,0 +1,31 @@
+//! A progress bar for converting files.
+
+use std::{
+    fs::File,
+    io::{stderr, stdout, BufRead, BufReader, IsTerminal},
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::Args;
+
+/// Returns whether to show the progress of reading the input `file`, which requires
+/// knowing its size. By default, it's only shown when it won't get mixed up with the
+/// output.
+pub fn is_enabled(args: &Args, file: &File) -> anyhow::Result<bool> {
+    if args.no_progress || !file.metadata()?.is_file() {
+        return Ok(false);
+    }
+    let is_output_terminal = (args.output.is_none() || args.tee) && stdout().is_terminal();
+    Ok(args.progress || (!is_output_terminal && stderr().is_terminal()))
+}
+
+/// Wraps `file` in a reader that shows the progress of reading it on STDERR.
+pub fn wrap(file: File) -> anyhow::Result<Box<dyn BufRead>> {
+    let len = file.metadata()?.len();
+    let style = ProgressStyle::with_template(
+        "{wide_bar} {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}",
+    )?;
+    let progress = ProgressBar::new(len).with_style(style);
+    Ok(Box::new(progress.wrap_read(BufReader::new(file))))
+}
@@ -0,0 +1,93 @@
+//! Decoding of encoded protobuf payloads.
+
+use std::{ops::Range, path::Path};
+
+use anyhow::{anyhow, Context};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+
+use crate::encoded;
+
+/// Shorter hex runs are too likely to be ordinary numbers or identifiers.
+const MIN_HEX_LEN: usize = 16;
+
+/// Decodes protobuf payloads of a single message type.
+#[derive(Debug)]
+pub struct Decoder {
+    message: MessageDescriptor,
+}
+
+impl Decoder {
+    /// Creates a decoder for the message named `message` in the serialized
+    /// `FileDescriptorSet` at `descriptor`.
+    pub fn new(descriptor: &Path, message: &str) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(descriptor)
+            .with_context(|| format!("failed to read {}", descriptor.display()))?;
+        let pool = DescriptorPool::decode(bytes.as_slice())
+            .with_context(|| format!("invalid descriptor set {}", descriptor.display()))?;
+        let message = pool
+            .get_message_by_name(message)
+            .ok_or_else(|| anyhow!("message type `{message}` not found in descriptor set"))?;
+        Ok(Self { message })
+    }
+
+    /// Returns the byte ranges of the base64- and hex-encoded messages in `line` along
+    /// with their JSON representations.
+    pub fn payloads(&self, line: &str) -> Vec<(Range<usize>, String)> {
+        let base64 = encoded::payloads(line).map(|payload| (payload.range, payload.bytes));
+        let mut payloads: Vec<_> = hex_payloads(line)
+            .chain(base64)
+            .filter_map(|(range, bytes)| Some((range, self.decode(&bytes)?)))
+            .collect();
+        payloads.sort_by_key(|(range, _)| range.start);
+        // Hex runs are also valid base64, so drop any overlapping decodings
+        payloads.dedup_by(|next, prev| next.0.start < prev.0.end);
+        payloads
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let message = DynamicMessage::decode(self.message.clone(), bytes).ok()?;
+        // Arbitrary bytes frequently decode as unknown fields, so only accept messages
+        // that conform to the schema
+        if message.fields().next().is_none() || message.unknown_fields().next().is_some() {
+            return None;
+        }
+        serde_json::to_string(&message).ok()
+    }
+}
+
+/// Returns an iterator over the even-length runs of hex digits in `line`, which must
+/// contain a letter to rule out plain numbers.
+fn hex_payloads(line: &str) -> impl Iterator<Item = (Range<usize>, Vec<u8>)> + '_ {
+    let bytes = line.as_bytes();
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while pos < bytes.len() {
+            let start = pos + bytes[pos..].iter().position(u8::is_ascii_hexdigit)?;
+            let end = start
+                + bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_hexdigit())
+                    .count();
+            pos = end;
+            let hex = &bytes[start..end];
+            if hex.len() < MIN_HEX_LEN
+                || !hex.len().is_multiple_of(2)
+                || hex.iter().all(u8::is_ascii_digit)
+                // Part of a longer word
+                || bytes.get(end).is_some_and(u8::is_ascii_alphanumeric)
+                || start > 0 && bytes[start - 1].is_ascii_alphanumeric()
+            {
+                continue;
+            }
+            let decoded = hex
+                .chunks_exact(2)
+                .map(|pair| {
+                    // Only hex digits were taken, so this can't fail
+                    u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap()
+                })
+                .collect();
+            return Some((start..end, decoded));
+        }
+        None
+    })
+}
@@ -0,0 +1,75 @@
+//! Time arguments that can be relative to now, like `2h` or `yesterday`.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+
+use crate::{duration, parse_date, Zone};
+
+/// A time given as an argument.
+#[derive(Clone, Copy, Debug)]
+pub enum TimeArg {
+    /// Seconds since the UNIX epoch.
+    Absolute(i64),
+    /// A duration in nanoseconds before now.
+    Ago(i64),
+    /// Midnight this many days before today, e.g. `1` for `yesterday`.
+    DaysAgo(i64),
+    /// Midnight of the most recent such day, which may be today.
+    Weekday(Weekday),
+}
+
+/// Parses an RFC 3339 date or date-time, a duration before now like `2h`, or a day like
+/// `today`, `yesterday`, `tomorrow`, or `monday`.
+pub fn parse(s: &str) -> Result<TimeArg, String> {
+    if let Ok(seconds) = parse_date(s) {
+        return Ok(TimeArg::Absolute(seconds));
+    }
+    if let Ok(nanos) = duration::parse(s) {
+        return Ok(TimeArg::Ago(nanos));
+    }
+    match s.to_ascii_lowercase().as_str() {
+        "now" => Ok(TimeArg::Ago(0)),
+        "today" => Ok(TimeArg::DaysAgo(0)),
+        "yesterday" => Ok(TimeArg::DaysAgo(1)),
+        "tomorrow" => Ok(TimeArg::DaysAgo(-1)),
+        day => day.parse().map(TimeArg::Weekday).map_err(|_| {
+            format!(
+                "expected an RFC 3339 date or date-time, a duration ago like `2h`, or a day \
+                 like `today` or `monday`, found `{s}`"
+            )
+        }),
+    }
+}
+
+impl TimeArg {
+    /// Returns the time in seconds since the UNIX epoch, where days start at midnight in
+    /// `zone`.
+    pub fn resolve(self, zone: Zone, now: DateTime<Utc>) -> i64 {
+        let today = zone.wall_time(now).date();
+        let date = match self {
+            TimeArg::Absolute(seconds) => return seconds,
+            TimeArg::Ago(nanos) => {
+                return now.timestamp() - nanos.div_euclid(1_000_000_000);
+            }
+            TimeArg::DaysAgo(days) => days_before(today, days),
+            TimeArg::Weekday(weekday) => {
+                let days = today.weekday().days_since(weekday);
+                days_before(today, i64::from(days))
+            }
+        };
+        let midnight = date.and_time(NaiveTime::MIN);
+        // Some zones skip midnight when clocks are set forward, so the day starts an hour
+        // later
+        zone.to_utc(midnight)
+            .or_else(|| zone.to_utc(midnight + chrono::Duration::hours(1)))
+            .unwrap_or_else(|| midnight.and_utc())
+            .timestamp()
+    }
+}
+
+fn days_before(date: NaiveDate, days: i64) -> NaiveDate {
+    if days < 0 {
+        date + Days::new(days.unsigned_abs())
+    } else {
+        date - Days::new(days.unsigned_abs())
+    }
+}
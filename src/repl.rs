@@ -0,0 +1,135 @@
+//! Interactive prompt that shows every interpretation of the values entered.
+
+use std::io::{stdin, stdout, BufRead, Write};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use epoch_to::{Precision, Unit};
+
+use crate::{Reformatter, Zone};
+
+/// The units values are interpreted in.
+pub const UNITS: [Unit; 4] = [Unit::Seconds, Unit::Millis, Unit::Micros, Unit::Nanos];
+
+const HELP: &str = "Enter an epoch in any unit, a 0x-prefixed hex epoch, or an RFC 3339 \
+                    date-time to see its interpretations. Press Ctrl-D to quit";
+
+/// Prompts for values on STDIN until it ends, printing their interpretations to STDOUT.
+pub fn run(reformatter: &Reformatter) -> anyhow::Result<()> {
+    eprintln!("{HELP}");
+    let mut input = stdin().lock();
+    let mut output = stdout().lock();
+    let mut line = String::new();
+    loop {
+        eprint!("> ");
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let value = line.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match interpret(reformatter, value) {
+            Some(rows) => {
+                let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+                for (label, value) in &rows {
+                    writeln!(output, "{label:<width$}  {value}")?;
+                }
+            }
+            None => writeln!(output, "not an epoch or RFC 3339 date-time: `{value}`")?,
+        }
+        output.flush()?;
+    }
+}
+
+/// Returns labeled interpretations of `value`: the time an epoch represents in each unit,
+/// or the epochs in each unit of a date-time.
+fn interpret(reformatter: &Reformatter, value: &str) -> Option<Vec<(String, String)>> {
+    if let Some(n) = parse_integer(value) {
+        let rows = UNITS
+            .iter()
+            .map(|&unit| {
+                let rendered = unit
+                    .to_nanos(n, reformatter.epoch_base)
+                    .map(|time_ns| {
+                        renderings(reformatter, time_ns, unit.precision())
+                            .into_iter()
+                            .map(|(zone, rendering)| format!("{zone} {rendering}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_else(|| "out of range".to_owned());
+                (unit_name(unit), rendered)
+            })
+            .collect();
+        return Some(rows);
+    }
+    let (time, precision) = parse_time(value)?;
+    let time_ns = time.timestamp_nanos_opt()?;
+    let mut rows: Vec<_> = UNITS
+        .iter()
+        .filter_map(|&unit| {
+            let n = unit.nanos_to_unit(time_ns, reformatter.epoch_base)?;
+            Some((unit_name(unit), n.to_string()))
+        })
+        .collect();
+    rows.extend(renderings(reformatter, time_ns, precision));
+    Some(rows)
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_integer(value: &str) -> Option<i64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses an RFC 3339 date-time or date along with its precision.
+pub fn parse_time(value: &str) -> Option<(DateTime<Utc>, Precision)> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        let precision = match time.timestamp_subsec_nanos() {
+            0 => Precision::Seconds,
+            ns if ns % 1_000_000 == 0 => Precision::Millis,
+            ns if ns % 1_000 == 0 => Precision::Micros,
+            _ => Precision::Nanos,
+        };
+        return Some((time.to_utc(), precision));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some((date.and_time(NaiveTime::MIN).and_utc(), Precision::Seconds))
+}
+
+/// Renders `time_ns` in UTC, the local zone, and any `--tz` zones, along with their
+/// names.
+pub fn renderings(
+    reformatter: &Reformatter,
+    time_ns: i64,
+    precision: Precision,
+) -> Vec<(String, String)> {
+    let time = DateTime::from_timestamp_nanos(time_ns);
+    let mut zones = vec![
+        ("UTC".to_owned(), Zone::Utc),
+        ("local".to_owned(), Zone::Local),
+    ];
+    for &zone in std::iter::once(&reformatter.zone).chain(&reformatter.other_zones) {
+        if let Zone::Tz(tz) = zone {
+            zones.push((tz.name().to_owned(), zone));
+        }
+    }
+    zones
+        .into_iter()
+        .map(|(name, zone)| (name, reformatter.render(time, precision, zone)))
+        .collect()
+}
+
+pub fn unit_name(unit: Unit) -> String {
+    use clap::ValueEnum;
+
+    unit.to_possible_value()
+        .map(|value| value.get_name().to_owned())
+        .unwrap_or_default()
+}
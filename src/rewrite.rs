@@ -0,0 +1,123 @@
+//! Regex-based rewriting of lines with named capture groups.
+
+use std::ops::Range;
+
+use anyhow::bail;
+use regex::{Captures, Regex};
+
+/// Replaces matches of a regex with an expanded template.
+#[derive(Debug)]
+pub struct Rewriter {
+    regex: Regex,
+    template: Vec<Segment>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    /// A capture group, inserted as-is or with any timestamps in it converted.
+    Group {
+        name: String,
+        is_timestamp: bool,
+    },
+}
+
+impl Rewriter {
+    /// Creates a rewriter replacing matches of `regex` with `template`, where `${NAME}`
+    /// inserts a capture group, `${NAME:ts}` inserts it with any timestamps converted,
+    /// and `$$` inserts a literal `$`.
+    pub fn new(regex: &str, template: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(regex)?;
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find('$') {
+            literal.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix('$') {
+                literal.push('$');
+                rest = after;
+                continue;
+            }
+            let Some((group, after)) = rest
+                .strip_prefix('{')
+                .and_then(|group| group.split_once('}'))
+            else {
+                bail!("expected `${{NAME}}` or `$$` in template `{template}`");
+            };
+            let (name, is_timestamp) = match group.split_once(':') {
+                Some((name, "ts")) => (name, true),
+                Some((_, flag)) => bail!("unknown flag `{flag}` in template `{template}`"),
+                None => (group, false),
+            };
+            let is_index = name
+                .parse::<usize>()
+                .is_ok_and(|i| i < regex.captures_len());
+            if !is_index && !regex.capture_names().flatten().any(|n| n == name) {
+                bail!("no capture group `{name}` in `{regex}`");
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Group {
+                name: name.to_owned(),
+                is_timestamp,
+            });
+            rest = after;
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self {
+            regex,
+            template: segments,
+        })
+    }
+
+    /// Returns the byte ranges of the matches in `line` along with their replacements,
+    /// using `convert` to convert timestamps in flagged groups, which is passed each group
+    /// and its byte offset in `line`.
+    pub fn rewrites(
+        &self,
+        line: &str,
+        convert: impl Fn(&str, usize) -> anyhow::Result<String>,
+    ) -> anyhow::Result<Vec<(Range<usize>, String)>> {
+        self.regex
+            .captures_iter(line)
+            // Skip empty matches, which would insert the template between every character
+            .filter(|captures| !captures[0].is_empty())
+            .map(|captures| {
+                let range = captures.get(0).expect("group 0 always matches").range();
+                Ok((range, self.expand(&captures, &convert)?))
+            })
+            .collect()
+    }
+
+    fn expand(
+        &self,
+        captures: &Captures,
+        convert: &impl Fn(&str, usize) -> anyhow::Result<String>,
+    ) -> anyhow::Result<String> {
+        let mut expanded = String::new();
+        for segment in &self.template {
+            match segment {
+                Segment::Literal(literal) => expanded.push_str(literal),
+                Segment::Group { name, is_timestamp } => {
+                    let group = match name.parse::<usize>() {
+                        Ok(i) => captures.get(i),
+                        Err(_) => captures.name(name),
+                    };
+                    match group {
+                        Some(group) if *is_timestamp => {
+                            expanded.push_str(&convert(group.as_str(), group.start())?);
+                        }
+                        Some(group) => expanded.push_str(group.as_str()),
+                        None => {}
+                    }
+                }
+            }
+        }
+        Ok(expanded)
+    }
+}
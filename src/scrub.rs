@@ -0,0 +1,120 @@
+//! Replacement of timestamps with placeholders, and their restoration.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use regex::Regex;
+
+/// Replaces timestamps with a constant placeholder, or with sequential tokens when the
+/// placeholder contains `{n}`.
+#[derive(Debug)]
+pub struct Scrubber {
+    placeholder: String,
+    /// The sequence number of each original timestamp, starting from 1.
+    tokens: RefCell<HashMap<String, usize>>,
+}
+
+impl Scrubber {
+    pub fn new(placeholder: String) -> Self {
+        Self {
+            placeholder,
+            tokens: RefCell::default(),
+        }
+    }
+
+    fn is_sequential(&self) -> bool {
+        self.placeholder.contains("{n}")
+    }
+
+    /// Returns the placeholder for `original`. Repeated timestamps get the same token.
+    pub fn placeholder(&self, original: &str) -> String {
+        if !self.is_sequential() {
+            return self.placeholder.clone();
+        }
+        let mut tokens = self.tokens.borrow_mut();
+        let next = tokens.len() + 1;
+        let n = *tokens.entry(original.to_owned()).or_insert(next);
+        self.token(n)
+    }
+
+    fn token(&self, n: usize) -> String {
+        self.placeholder.replace("{n}", &n.to_string())
+    }
+
+    /// Checks that the placeholders can be mapped back to the original timestamps.
+    pub fn check_reversible(&self) -> anyhow::Result<()> {
+        if !self.is_sequential() {
+            bail!("recording a map file requires `{{n}}` in the `--scrub` placeholder");
+        }
+        Ok(())
+    }
+
+    /// Writes a JSON object from each placeholder issued so far to its original
+    /// timestamp.
+    pub fn write_map(&self, path: &Path) -> anyhow::Result<()> {
+        let tokens = self.tokens.borrow();
+        let mut originals: Vec<_> = tokens.iter().collect();
+        originals.sort_by_key(|(_, n)| **n);
+        let map: serde_json::Map<_, _> = originals
+            .into_iter()
+            .map(|(original, n)| (self.token(*n), original.clone().into()))
+            .collect();
+        let write = || {
+            let mut writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(&mut writer, &map)?;
+            writer.flush()?;
+            anyhow::Ok(())
+        };
+        write().with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// Restores the original timestamps of placeholders recorded in a map file.
+#[derive(Debug)]
+pub struct Unscrubber {
+    originals: HashMap<String, String>,
+    regex: Regex,
+}
+
+impl Unscrubber {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let read = || anyhow::Ok(serde_json::from_reader(File::open(path)?)?);
+        let originals: HashMap<String, String> =
+            read().with_context(|| format!("failed to read {}", path.display()))?;
+        let mut placeholders: Vec<_> = originals.keys().map(|p| regex::escape(p)).collect();
+        if placeholders.is_empty() {
+            bail!("map file {} has no placeholders", path.display());
+        }
+        // Prefer the longest placeholder so `T1` doesn't match the start of `T10`
+        placeholders.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        let regex = Regex::new(&placeholders.join("|"))?;
+        Ok(Self { originals, regex })
+    }
+
+    /// Returns `line` with any placeholders replaced with their original timestamps.
+    /// Placeholders that are part of a longer number, like `T1` in `T10`, are skipped.
+    pub fn restore(&self, line: &str) -> String {
+        let is_digit_at = |i: usize| line.as_bytes().get(i).is_some_and(u8::is_ascii_digit);
+        let mut restored = String::with_capacity(line.len());
+        let mut text_start = 0;
+        for m in self.regex.find_iter(line) {
+            let continues_before =
+                m.start() > 0 && is_digit_at(m.start()) && is_digit_at(m.start() - 1);
+            let continues_after = m.end() > 0 && is_digit_at(m.end() - 1) && is_digit_at(m.end());
+            if continues_before || continues_after {
+                continue;
+            }
+            restored.push_str(&line[text_start..m.start()]);
+            restored.push_str(&self.originals[m.as_str()]);
+            text_start = m.end();
+        }
+        restored.push_str(&line[text_start..]);
+        restored
+    }
+}
@@ -0,0 +1,123 @@
+//! Converting the timestamps in streams received over TCP and UDP for `epoch serve`.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use anyhow::Context;
+
+use crate::{output, Args, Reformatter};
+
+/// The largest UDP payload.
+const MAX_DATAGRAM: usize = 65_507;
+
+/// Listens for newline-separated messages on TCP connections and for a message per UDP
+/// datagram at `addr`, writing each with its timestamps converted to the output of
+/// `args` as it arrives.
+pub fn serve(args: &Args, addr: SocketAddr) -> anyhow::Result<()> {
+    // Fail on invalid options before listening, since each thread has its own reformatter
+    Reformatter::new(args)?;
+    let tcp = TcpListener::bind(addr).with_context(|| format!("failed to listen on TCP {addr}"))?;
+    let addr = tcp.local_addr()?;
+    let udp = UdpSocket::bind(addr).with_context(|| format!("failed to listen on UDP {addr}"))?;
+    eprintln!("listening on {addr}");
+
+    let (sender, receiver) = mpsc::channel();
+    {
+        let args = args.clone();
+        let sender = sender.clone();
+        thread::spawn(move || receive_datagrams(&args, &udp, &sender));
+    }
+    {
+        let args = args.clone();
+        thread::spawn(move || {
+            for stream in tcp.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let args = args.clone();
+                        let sender = sender.clone();
+                        thread::spawn(move || receive_stream(&args, stream, &sender));
+                    }
+                    Err(e) => eprintln!("warning: failed to accept a connection: {e}"),
+                }
+            }
+        });
+    }
+
+    // A file output is never finished, so it's written in place
+    let mut output = output::open(&Args {
+        no_atomic: true,
+        ..args.clone()
+    })?;
+    for message in receiver {
+        output.write_all(message.as_bytes())?;
+        output.flush()?;
+    }
+    output.finish()
+}
+
+/// Sends the lines of `stream` with their timestamps converted until it's closed.
+fn receive_stream(args: &Args, stream: TcpStream, sender: &Sender<String>) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "a connection".to_owned(), |peer| peer.to_string());
+    let Ok(reformatter) = Reformatter::new(args) else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut buf = Vec::new();
+    for line_no in 1.. {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("warning: failed to read from {peer}: {e}");
+                break;
+            }
+        }
+        reformatter.start_line(line_no);
+        if !send_converted(&reformatter, &buf, sender) {
+            break;
+        }
+    }
+}
+
+/// Sends each datagram received on `socket` with its timestamps converted.
+fn receive_datagrams(args: &Args, socket: &UdpSocket, sender: &Sender<String>) {
+    let Ok(reformatter) = Reformatter::new(args) else {
+        return;
+    };
+    let mut buf = vec![0; MAX_DATAGRAM];
+    for line_no in 1.. {
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e) => {
+                eprintln!("warning: failed to receive a datagram: {e}");
+                continue;
+            }
+        };
+        reformatter.start_line(line_no);
+        if !send_converted(&reformatter, &buf[..len], sender) {
+            break;
+        }
+    }
+}
+
+/// Sends `message` with its timestamps converted and a single newline at the end,
+/// returning `false` if the output is gone.
+fn send_converted(reformatter: &Reformatter, message: &[u8], sender: &Sender<String>) -> bool {
+    let message = String::from_utf8_lossy(message);
+    let message = message.trim_end_matches(['\r', '\n']);
+    let converted = match reformatter.convert(message) {
+        Ok(converted) => converted,
+        Err(e) => {
+            eprintln!("warning: {e:#}");
+            message.to_owned()
+        }
+    };
+    sender.send(format!("{converted}\n")).is_ok()
+}
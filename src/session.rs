@@ -0,0 +1,84 @@
+//! Trading session annotations from a small built-in calendar of venues' regular hours.
+//! Holidays and early closes aren't included.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::{
+    America::{Chicago, New_York},
+    Europe::London,
+    Tz,
+};
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Venue {
+    /// CME Globex futures: Sunday to Friday 17:00-16:00 Chicago time with a daily
+    /// maintenance halt, where evening sessions belong to the next trade date
+    #[value(alias = "cme_globex")]
+    CmeGlobex,
+    /// New York Stock Exchange: weekdays 09:30-16:00 New York time
+    Nyse,
+    /// Nasdaq: weekdays 09:30-16:00 New York time
+    Nasdaq,
+    /// London Stock Exchange: weekdays 08:00-16:30 London time
+    Lse,
+}
+
+impl Venue {
+    fn name(self) -> &'static str {
+        match self {
+            Venue::CmeGlobex => "CME Globex",
+            Venue::Nyse => "NYSE",
+            Venue::Nasdaq => "Nasdaq",
+            Venue::Lse => "LSE",
+        }
+    }
+
+    fn zone(self) -> Tz {
+        match self {
+            Venue::CmeGlobex => Chicago,
+            Venue::Nyse | Venue::Nasdaq => New_York,
+            Venue::Lse => London,
+        }
+    }
+
+    /// Returns the date of the session `time` falls in, or `None` if the venue is closed.
+    pub fn session(self, time: DateTime<Utc>) -> Option<NaiveDate> {
+        let wall_time = time.with_timezone(&self.zone()).naive_local();
+        let (date, time) = (wall_time.date(), wall_time.time());
+        let hm = |h, m| NaiveTime::from_hms_opt(h, m, 0).expect("valid time");
+        match self {
+            Venue::CmeGlobex => {
+                let is_evening = time >= hm(17, 0);
+                let is_open = match date.weekday() {
+                    Weekday::Sat => false,
+                    Weekday::Sun => is_evening,
+                    Weekday::Fri => time < hm(16, 0),
+                    _ => is_evening || time < hm(16, 0),
+                };
+                let trade_date = if is_evening {
+                    date.checked_add_days(Days::new(1))?
+                } else {
+                    date
+                };
+                is_open.then_some(trade_date)
+            }
+            Venue::Nyse | Venue::Nasdaq | Venue::Lse => {
+                let (open, close) = match self {
+                    Venue::Lse => (hm(8, 0), hm(16, 30)),
+                    _ => (hm(9, 30), hm(16, 0)),
+                };
+                let is_weekday = !matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+                (is_weekday && (open..close).contains(&time)).then_some(date)
+            }
+        }
+    }
+
+    /// Returns whether the venue is open at `time` and the date of the session, e.g.
+    /// `NYSE open, session 2024-02-28`.
+    pub fn label(self, time: DateTime<Utc>) -> String {
+        match self.session(time) {
+            Some(date) => format!("{} open, session {date}", self.name()),
+            None => format!("{} closed", self.name()),
+        }
+    }
+}
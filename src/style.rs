@@ -0,0 +1,115 @@
+//! Preset and custom renderings of timestamps.
+
+use std::fmt::Display;
+
+use chrono::{format::StrftimeItems, DateTime, Locale, TimeZone};
+use clap::ValueEnum;
+use epoch_to::Precision;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Style {
+    /// RFC 3339 with the precision of the input, e.g. `2024-02-28T20:43:09.456Z`
+    #[default]
+    Rfc3339,
+    /// RFC 2822 as used in email headers, e.g. `Wed, 28 Feb 2024 20:43:09 +0000`
+    Rfc2822,
+    /// Like the output of `date`, e.g. `Wed Feb 28 20:43:09 UTC 2024`
+    UnixDate,
+    /// ISO 8601 without separators, e.g. `20240228T204309.456Z`
+    Compact,
+    /// Whole days since the UNIX epoch, e.g. `19781`
+    EpochDays,
+    /// The ISO 8601 week date, e.g. `2024-W09-3`
+    IsoWeek,
+    /// The ISO 8601 ordinal date, e.g. `2024-059`
+    Ordinal,
+}
+
+/// Renders timestamps in a style or a custom format.
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    pub style: Style,
+    /// A `strftime` format that overrides the style.
+    pub format: Option<String>,
+    /// The language of month and day names in the custom format and `unix-date`.
+    pub locale: Locale,
+}
+
+impl Renderer {
+    /// Formats `time` with `precision`, where `is_utc` selects a `Z` suffix over a
+    /// numeric offset.
+    pub fn format<Tz>(&self, time: &DateTime<Tz>, precision: Precision, is_utc: bool) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        match &self.format {
+            Some(format) => time.format_localized(format, self.locale).to_string(),
+            None => self.style.format(time, precision, is_utc, self.locale),
+        }
+    }
+}
+
+impl Style {
+    /// Formats `time` with `precision`, where `is_utc` selects a `Z` suffix over a
+    /// numeric offset. Only `unix-date` is rendered in `locale`, since the others are
+    /// meant to be machine-readable.
+    pub fn format<Tz>(
+        self,
+        time: &DateTime<Tz>,
+        precision: Precision,
+        is_utc: bool,
+        locale: Locale,
+    ) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        let format = match (self, precision, is_utc) {
+            (Style::EpochDays, ..) => {
+                let days = time.date_naive() - DateTime::UNIX_EPOCH.date_naive();
+                return days.num_days().to_string();
+            }
+            (Style::UnixDate, Precision::Days, _) => {
+                return time.format_localized("%a %b %e %Y", locale).to_string();
+            }
+            // Unlike the other styles, this renders the zone's abbreviation
+            (Style::UnixDate, ..) => {
+                return time
+                    .format_localized("%a %b %e %H:%M:%S %Z %Y", locale)
+                    .to_string();
+            }
+            (Style::IsoWeek, ..) => "%G-W%V-%u",
+            (Style::Ordinal, ..) => "%Y-%j",
+            (Style::Rfc3339, _, true) => precision.rfc_format::<false>(),
+            (Style::Rfc3339, _, false) => precision.rfc_format::<true>(),
+            (Style::Rfc2822, Precision::Days, _) => "%a, %d %b %Y",
+            (Style::Rfc2822, ..) => "%a, %d %b %Y %H:%M:%S %z",
+            (Style::Compact, Precision::Days, _) => "%Y%m%d",
+            (Style::Compact, Precision::Seconds, true) => "%Y%m%dT%H%M%SZ",
+            (Style::Compact, Precision::Seconds, false) => "%Y%m%dT%H%M%S%z",
+            (Style::Compact, Precision::Millis, true) => "%Y%m%dT%H%M%S%.3fZ",
+            (Style::Compact, Precision::Millis, false) => "%Y%m%dT%H%M%S%.3f%z",
+            (Style::Compact, Precision::Micros, true) => "%Y%m%dT%H%M%S%.6fZ",
+            (Style::Compact, Precision::Micros, false) => "%Y%m%dT%H%M%S%.6f%z",
+            (Style::Compact, Precision::Nanos, true) => "%Y%m%dT%H%M%S%.9fZ",
+            (Style::Compact, Precision::Nanos, false) => "%Y%m%dT%H%M%S%.9f%z",
+        };
+        // Use the fixed offset so `%Z` renders as an RFC 3339 offset rather than the
+        // zone's abbreviation
+        time.fixed_offset().format(format).to_string()
+    }
+}
+
+/// Checks that `format` is a valid `strftime` format.
+pub fn parse_format(format: &str) -> Result<String, String> {
+    StrftimeItems::new(format)
+        .parse()
+        .map(|_| format.to_owned())
+        .map_err(|_| format!("invalid format `{format}`"))
+}
+
+pub fn parse_locale(locale: &str) -> Result<Locale, String> {
+    Locale::try_from(locale)
+        .map_err(|_| format!("unknown locale `{locale}`, expected a POSIX name like `de_DE`"))
+}
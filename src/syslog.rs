@@ -0,0 +1,58 @@
+//! Detection of RFC 3164 syslog dates like `Feb 28 20:43:09`, which have no year or zone.
+
+use std::{ops::Range, sync::LazyLock};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use regex::Regex;
+
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec) ( [1-9]|[12]\d|3[01]) (\d\d:\d\d:\d\d)\b",
+    )
+    .unwrap()
+});
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Infers the year of syslog dates from the detection window.
+#[derive(Debug)]
+pub struct Syslog {
+    /// Seconds since the UNIX epoch dates must be within.
+    window: Range<i64>,
+    now: DateTime<Utc>,
+}
+
+impl Syslog {
+    pub fn new(window: Range<i64>) -> Self {
+        Self {
+            window,
+            now: Utc::now(),
+        }
+    }
+
+    /// Returns an iterator over the syslog dates in `line`, where `to_utc` converts a
+    /// wall-clock time to UTC along with a flag that's passed through, like whether it
+    /// was skipped by a daylight saving time change. Each date is in the most recent year
+    /// within the window that isn't in the future, or otherwise the nearest one.
+    pub fn dates<'a, T: 'a>(
+        &'a self,
+        line: &'a str,
+        to_utc: impl Fn(NaiveDateTime) -> Option<(DateTime<Utc>, T)> + 'a,
+    ) -> impl Iterator<Item = (Range<usize>, (DateTime<Utc>, T))> + 'a {
+        DATE.captures_iter(line).filter_map(move |caps| {
+            let month = MONTHS.iter().position(|&m| m == &caps[1])? as u32 + 1;
+            let day: u32 = caps[2].trim_start().parse().ok()?;
+            let time = NaiveTime::parse_from_str(&caps[3], "%H:%M:%S").ok()?;
+            // Leap days may be up to 4 years away
+            let year = self.now.year();
+            let time = (year - 4..=year + 4)
+                .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+                .filter_map(|date| to_utc(date.and_time(time)))
+                .filter(|(time, _)| self.window.contains(&time.timestamp()))
+                .min_by_key(|(time, _)| (*time > self.now, (*time - self.now).abs()))?;
+            Some((caps.get(0).unwrap().range(), time))
+        })
+    }
+}
@@ -0,0 +1,99 @@
+//! Templates for what each timestamp is replaced with, for `--template`.
+
+use anyhow::bail;
+
+/// A replacement template like `{iso} (epoch={original})`.
+#[derive(Debug)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// The values a template can insert.
+#[derive(Clone, Copy, Debug)]
+pub enum Placeholder {
+    /// The timestamp formatted as it would be without a template.
+    Iso,
+    /// The timestamp as it appeared in the input.
+    Original,
+    /// The name of the unit the timestamp was detected in.
+    Unit,
+    /// Whole seconds since the UNIX epoch.
+    EpochS,
+    /// Nanoseconds since the UNIX epoch.
+    EpochNs,
+    /// How long ago or from now the timestamp is, e.g. `2 days 3 hours ago`.
+    Relative,
+}
+
+const PLACEHOLDERS: [(&str, Placeholder); 6] = [
+    ("iso", Placeholder::Iso),
+    ("original", Placeholder::Original),
+    ("unit", Placeholder::Unit),
+    ("epoch_s", Placeholder::EpochS),
+    ("epoch_ns", Placeholder::EpochNs),
+    ("relative", Placeholder::Relative),
+];
+
+impl Template {
+    /// Parses `template`, where `{NAME}` inserts a placeholder and `{{` and `}}` insert
+    /// literal braces.
+    pub fn new(template: &str) -> anyhow::Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = &rest[i..=i];
+            rest = &rest[i + 1..];
+            if let Some(after) = rest.strip_prefix(brace) {
+                literal.push_str(brace);
+                rest = after;
+                continue;
+            }
+            let Some((name, after)) = rest.split_once('}').filter(|_| brace == "{") else {
+                bail!("expected `{{NAME}}`, `{{{{`, or `}}}}` in template `{template}`");
+            };
+            let Some(&(_, placeholder)) = PLACEHOLDERS.iter().find(|(n, _)| *n == name) else {
+                let names: Vec<_> = PLACEHOLDERS
+                    .iter()
+                    .map(|(name, _)| format!("`{{{name}}}`"))
+                    .collect();
+                bail!(
+                    "unknown placeholder `{{{name}}}` in template `{template}`, expected one of {}",
+                    names.join(", ")
+                );
+            };
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Placeholder(placeholder));
+            rest = after;
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Expands the template, using `value` to render each placeholder.
+    pub fn expand(
+        &self,
+        mut value: impl FnMut(Placeholder) -> anyhow::Result<String>,
+    ) -> anyhow::Result<String> {
+        let mut expanded = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => expanded.push_str(literal),
+                Segment::Placeholder(placeholder) => expanded.push_str(&value(*placeholder)?),
+            }
+        }
+        Ok(expanded)
+    }
+}
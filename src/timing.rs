@@ -0,0 +1,52 @@
+//! Throughput metrics reported by `--timing`.
+
+use std::{
+    io::{self, BufRead, Read},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static LINES: AtomicU64 = AtomicU64::new(0);
+
+/// Counts the bytes and lines read through the wrapped reader.
+pub struct Counted<R>(pub R);
+
+fn count(read: &[u8]) {
+    BYTES.fetch_add(read.len() as u64, Ordering::Relaxed);
+    let lines = read.iter().filter(|&&b| b == b'\n').count();
+    LINES.fetch_add(lines as u64, Ordering::Relaxed);
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.0.read(buf)?;
+        count(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Counted<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // Filling again returns the buffered data without reading
+        if let Ok(buf) = self.0.fill_buf() {
+            count(&buf[..amt.min(buf.len())]);
+        }
+        self.0.consume(amt);
+    }
+}
+
+/// Prints the amount of input processed since `start` and the rate to STDERR.
+pub fn report(start: Instant, conversions: usize) {
+    let secs = start.elapsed().as_secs_f64();
+    let bytes = BYTES.load(Ordering::Relaxed);
+    eprintln!(
+        "{bytes} bytes, {} lines, {conversions} conversions in {secs:.3}s ({:.1} MB/s)",
+        LINES.load(Ordering::Relaxed),
+        bytes as f64 / 1e6 / secs,
+    );
+}
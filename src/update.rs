@@ -0,0 +1,242 @@
+//! Replacing the running binary with the latest GitHub release for `epoch self-update`.
+
+use std::{
+    env, fs,
+    io::{Cursor, Read},
+    path::Path,
+    process,
+};
+
+use anyhow::{bail, Context};
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Installs the latest release in place of the running binary if it's newer, or only
+/// reports whether there is one with `check`.
+pub fn self_update(check: bool) -> anyhow::Result<()> {
+    let release = latest_release()?;
+    let latest = release["tag_name"]
+        .as_str()
+        .context("the latest release has no tag")?
+        .trim_start_matches('v');
+    let (_, is_release) = parse_version(latest);
+    if !is_release {
+        println!("epoch {VERSION} is up to date, skipping the prerelease {latest}");
+        return Ok(());
+    }
+    if parse_version(latest) <= parse_version(VERSION) {
+        println!("epoch {VERSION} is up to date");
+        return Ok(());
+    }
+    if check {
+        println!("epoch {latest} is available, this is {VERSION}");
+        return Ok(());
+    }
+    // Release archives are named by the workflow in `.github/workflows/release.yaml`
+    let extension = if cfg!(windows) { "zip" } else { "tar.gz" };
+    let archive_name = format!("epoch-{latest}-{}.{extension}", target()?);
+    let archive = download(asset_url(&release, &archive_name)?)?;
+    let sums = download(asset_url(&release, &format!("{archive_name}.sha256"))?)?;
+    verify(&archive, &String::from_utf8_lossy(&sums))
+        .with_context(|| format!("failed to verify {archive_name}"))?;
+    let binary = extract(&archive).with_context(|| format!("failed to extract {archive_name}"))?;
+    replace_current_exe(&binary)?;
+    println!("updated epoch from {VERSION} to {latest}");
+    Ok(())
+}
+
+/// Returns the GitHub API's description of the latest release.
+fn latest_release() -> anyhow::Result<serde_json::Value> {
+    let repository = env!("CARGO_PKG_REPOSITORY");
+    let repository = repository
+        .strip_prefix("https://github.com/")
+        .unwrap_or(repository);
+    let url = format!("https://api.github.com/repos/{repository}/releases/latest");
+    let json = download_with(&url, "application/vnd.github+json")?;
+    serde_json::from_slice(&json).context("failed to parse the latest release")
+}
+
+fn asset_url<'a>(release: &'a serde_json::Value, name: &str) -> anyhow::Result<&'a str> {
+    release["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|asset| asset["name"] == name)
+        .and_then(|asset| asset["browser_download_url"].as_str())
+        .with_context(|| format!("the latest release has no `{name}`"))
+}
+
+fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    download_with(url, "application/octet-stream")
+}
+
+fn download_with(url: &str, accept: &str) -> anyhow::Result<Vec<u8>> {
+    let response = ureq::get(url)
+        .header("Accept", accept)
+        .call()
+        .with_context(|| format!("failed to request {url}"))?;
+    let mut body = Vec::new();
+    response
+        .into_body()
+        .into_reader()
+        .read_to_end(&mut body)
+        .with_context(|| format!("failed to download {url}"))?;
+    Ok(body)
+}
+
+/// Returns the target triple of the release built for this platform.
+fn target() -> anyhow::Result<String> {
+    let arch = env::consts::ARCH;
+    match env::consts::OS {
+        "linux" if cfg!(target_env = "musl") => Ok(format!("{arch}-unknown-linux-musl")),
+        "linux" => Ok(format!("{arch}-unknown-linux-gnu")),
+        "macos" => Ok(format!("{arch}-apple-darwin")),
+        "windows" => Ok(format!("{arch}-pc-windows-msvc")),
+        os => bail!("no releases are built for {arch} {os}"),
+    }
+}
+
+/// Checks `archive` against the first SHA-256 hash in `sums`, which is either the output
+/// of `shasum` or `certutil`.
+fn verify(archive: &[u8], sums: &str) -> anyhow::Result<()> {
+    let expected = sums
+        .split_whitespace()
+        .find(|word| word.len() == 64 && word.bytes().all(|b| b.is_ascii_hexdigit()))
+        .context("no SHA-256 hash in the checksum file")?;
+    let actual = Sha256::digest(archive)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("expected SHA-256 hash {expected}, found {actual}");
+    }
+    Ok(())
+}
+
+/// Returns the contents of the `epoch` binary in a release archive.
+fn extract(archive: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let name = format!("epoch{}", env::consts::EXE_SUFFIX);
+    let is_binary = |path: &Path| path.file_name().is_some_and(|file| file == name.as_str());
+    let mut binary = Vec::new();
+    if cfg!(windows) {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive))?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            if is_binary(Path::new(file.name()?.as_ref())) {
+                file.read_to_end(&mut binary)?;
+                return Ok(binary);
+            }
+        }
+    } else {
+        let mut tar = tar::Archive::new(GzDecoder::new(archive));
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if is_binary(&entry.path()?) {
+                entry.read_to_end(&mut binary)?;
+                return Ok(binary);
+            }
+        }
+    }
+    bail!("no `{name}` in the archive")
+}
+
+/// Atomically replaces the running binary with `binary`.
+fn replace_current_exe(binary: &[u8]) -> anyhow::Result<()> {
+    let exe = env::current_exe().context("failed to locate the running binary")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    let dir = exe
+        .parent()
+        .context("the running binary has no directory")?;
+    let new = dir.join(format!(".epoch.{}.new", process::id()));
+    let result = (|| {
+        fs::write(&new, binary)?;
+        fs::set_permissions(&new, fs::metadata(&exe)?.permissions())?;
+        // Windows can't replace a running binary, but it can rename it
+        if cfg!(windows) {
+            let old = exe.with_extension("old");
+            let _ = fs::remove_file(&old);
+            fs::rename(&exe, &old)?;
+        }
+        fs::rename(&new, &exe)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&new);
+    }
+    result.with_context(|| format!("failed to replace {}", exe.display()))
+}
+
+/// Parses a version like `0.3.1` or `0.4.0-rc.1` for comparison into its numeric
+/// components and whether it's a release rather than a prerelease, which sorts before
+/// the release.
+fn parse_version(version: &str) -> (Vec<u64>, bool) {
+    // Build metadata doesn't affect precedence
+    let version = version
+        .split_once('+')
+        .map_or(version, |(version, _)| version);
+    let (numbers, is_release) = match version.split_once('-') {
+        Some((numbers, _)) => (numbers, false),
+        None => (version, true),
+    };
+    let numbers = numbers
+        .split('.')
+        .map(|part| part.parse().unwrap_or_default())
+        .collect();
+    (numbers, is_release)
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.3.1"), (vec![0, 3, 1], true));
+        assert_eq!(parse_version("0.3.1+build.5"), (vec![0, 3, 1], true));
+        assert!(parse_version("0.10.0") > parse_version("0.9.12"));
+    }
+
+    #[test]
+    fn test_parse_version_prerelease() {
+        assert_eq!(parse_version("1.10.0-rc.1"), (vec![1, 10, 0], false));
+        assert!(parse_version("0.5.0-rc.1") < parse_version("0.5.0"));
+        assert!(parse_version("0.5.0-rc.1") > parse_version("0.4.9"));
+    }
+
+    #[test]
+    fn test_verify() {
+        let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        verify(b"hello", &format!("{sha256}  epoch.tar.gz\n")).unwrap();
+        // `certutil` prints uppercase hashes after a header
+        let certutil = format!("SHA256 hash of epoch.zip:\n{}\n", sha256.to_uppercase());
+        verify(b"hello", &certutil).unwrap();
+        let error = verify(b"hullo", sha256).unwrap_err();
+        assert!(error.to_string().starts_with("expected SHA-256 hash"));
+        assert!(verify(b"hello", "no hash").is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_extract() {
+        let tar_gz = |files: &[(&str, &[u8])]| {
+            let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+            for (path, contents) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o755);
+                tar.append_data(&mut header, path, *contents).unwrap();
+            }
+            tar.into_inner().unwrap().finish().unwrap()
+        };
+        let archive = tar_gz(&[
+            ("epoch-0.4.0/README.md", b"readme"),
+            ("epoch-0.4.0/epoch", b"binary"),
+        ]);
+        assert_eq!(extract(&archive).unwrap(), b"binary");
+        let archive = tar_gz(&[("epoch-0.4.0/README.md", b"readme")]);
+        assert!(extract(&archive).is_err());
+    }
+}
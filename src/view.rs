@@ -0,0 +1,231 @@
+//! Interactive pager for browsing a file with its timestamps converted and highlighted.
+
+use std::{
+    fs,
+    io::{stdout, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use chrono::Utc;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use epoch_to::Precision;
+
+use crate::{relative, Reformatter, Zone};
+
+/// Precisions cycled through with `p`, where `None` keeps the precision of the input.
+const PRECISIONS: [Option<Precision>; 5] = [
+    None,
+    Some(Precision::Seconds),
+    Some(Precision::Millis),
+    Some(Precision::Micros),
+    Some(Precision::Nanos),
+];
+
+const HELP: &str = "q quit  l zone  p precision  t jump to time";
+
+struct Viewer<'a> {
+    name: String,
+    lines: Vec<String>,
+    reformatter: &'a mut Reformatter,
+    /// The zone to toggle to from the configured one.
+    other_zone: Zone,
+    /// Index into `PRECISIONS`.
+    precision: usize,
+    /// Index of the first line on screen.
+    top: usize,
+    /// The text of the jump prompt while it's open.
+    prompt: Option<String>,
+    /// A message shown in place of the help until the next key.
+    message: Option<String>,
+}
+
+/// Shows `path` in a scrollable pager until the user quits.
+pub fn view(path: &Path, reformatter: &mut Reformatter) -> anyhow::Result<()> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let other_zone = match reformatter.zone {
+        Zone::Utc => Zone::Local,
+        Zone::Local | Zone::Tz(_) => Zone::Utc,
+    };
+    let precision = PRECISIONS
+        .iter()
+        .position(|&precision| precision == reformatter.precision)
+        .unwrap_or_default();
+    // Mark conversions with reverse video
+    reformatter.highlight = Some(("\x1b[7m", "\x1b[27m"));
+    let mut viewer = Viewer {
+        name: path.display().to_string(),
+        lines: text.lines().map(str::to_owned).collect(),
+        reformatter,
+        other_zone,
+        precision,
+        top: 0,
+        prompt: None,
+        message: None,
+    };
+
+    terminal::enable_raw_mode()?;
+    execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = viewer.run();
+    // Restore the terminal even if drawing failed
+    execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+impl Viewer<'_> {
+    fn run(&mut self) -> anyhow::Result<()> {
+        loop {
+            let (_, rows) = terminal::size()?;
+            // The last row is the status line
+            let page = usize::from(rows.saturating_sub(1)).max(1);
+            self.draw(page)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.prompt.is_some() {
+                self.handle_prompt(key);
+                continue;
+            }
+            self.message = None;
+            let last_top = self.lines.len().saturating_sub(page);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.top += 1,
+                KeyCode::Char('k') | KeyCode::Up => self.top = self.top.saturating_sub(1),
+                KeyCode::Char(' ') | KeyCode::PageDown => self.top += page,
+                KeyCode::Char('b') | KeyCode::PageUp => self.top = self.top.saturating_sub(page),
+                KeyCode::Char('g') | KeyCode::Home => self.top = 0,
+                KeyCode::Char('G') | KeyCode::End => self.top = last_top,
+                KeyCode::Char('l') => {
+                    std::mem::swap(&mut self.reformatter.zone, &mut self.other_zone);
+                }
+                KeyCode::Char('p') => {
+                    self.precision = (self.precision + 1) % PRECISIONS.len();
+                    self.reformatter.precision = PRECISIONS[self.precision];
+                }
+                KeyCode::Char('t') => self.prompt = Some(String::new()),
+                _ => {}
+            }
+            self.top = self.top.min(last_top);
+        }
+    }
+
+    fn handle_prompt(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => prompt.push(c),
+            KeyCode::Backspace => {
+                prompt.pop();
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let input = self.prompt.take().unwrap_or_default();
+                match relative::parse(input.trim()) {
+                    Ok(time) => {
+                        let s = time.resolve(self.reformatter.zone, Utc::now());
+                        self.jump(s.saturating_mul(1_000_000_000));
+                    }
+                    Err(e) => self.message = Some(e),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Scrolls to the first line whose first timestamp is at or after `time_ns`.
+    fn jump(&mut self, time_ns: i64) {
+        let found = self.lines.iter().enumerate().position(|(i, line)| {
+            self.reformatter.start_line(i + 1);
+            self.reformatter
+                .matches(line)
+                .next()
+                .is_some_and(|m| m.time_ns >= time_ns)
+        });
+        match found {
+            Some(i) => self.top = i,
+            None => self.message = Some("no timestamp at or after that time".to_owned()),
+        }
+    }
+
+    fn draw(&self, page: usize) -> anyhow::Result<()> {
+        let (columns, _) = terminal::size()?;
+        let width = usize::from(columns);
+        let mut out = stdout().lock();
+        queue!(out, terminal::Clear(ClearType::All))?;
+        for (row, line) in self.lines.iter().skip(self.top).take(page).enumerate() {
+            self.reformatter.start_line(self.top + row + 1);
+            let converted = self.reformatter.convert(line)?;
+            queue!(
+                out,
+                cursor::MoveTo(0, row as u16),
+                Print(truncate(&converted, width)),
+                // Don't let a truncated highlight bleed into the next line
+                SetAttribute(Attribute::Reset),
+            )?;
+        }
+        let status = if let Some(prompt) = &self.prompt {
+            format!("jump to (RFC 3339 date-time, duration ago, or day): {prompt}")
+        } else {
+            let zone = match self.reformatter.zone {
+                Zone::Utc => "UTC".to_owned(),
+                Zone::Local => "local".to_owned(),
+                Zone::Tz(tz) => tz.name().to_owned(),
+            };
+            let precision = match PRECISIONS[self.precision] {
+                None => "auto",
+                Some(Precision::Seconds) => "s",
+                Some(Precision::Millis) => "ms",
+                Some(Precision::Micros) => "us",
+                Some(Precision::Nanos | Precision::Days) => "ns",
+            };
+            format!(
+                "{} {}/{} | {zone} | {precision} | {}",
+                self.name,
+                (self.top + 1).min(self.lines.len()),
+                self.lines.len(),
+                self.message.as_deref().unwrap_or(HELP),
+            )
+        };
+        queue!(
+            out,
+            cursor::MoveTo(0, page as u16),
+            SetAttribute(Attribute::Reverse),
+            Print(format!("{:<width$}", truncate(&status, width))),
+            SetAttribute(Attribute::Reset),
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Returns the prefix of `text` that fits in `width` columns, not counting escape
+/// sequences.
+fn truncate(text: &str, width: usize) -> &str {
+    let mut visible = 0;
+    let mut in_escape = false;
+    for (i, c) in text.char_indices() {
+        if in_escape {
+            in_escape = !c.is_ascii_alphabetic();
+        } else if c == '\x1b' {
+            in_escape = true;
+        } else if visible == width {
+            return &text[..i];
+        } else {
+            visible += 1;
+        }
+    }
+    text
+}